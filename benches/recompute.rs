@@ -0,0 +1,75 @@
+//! Measures recomputation of a layered model with thousands of values, in which every value
+//! depends on two values of the previous layer.
+
+use charsheet::model::*;
+use charsheet::Character;
+use std::time::{Duration, Instant};
+
+const LAYERS: usize = 12;
+const WIDTH: usize = 250;
+const ITERATIONS: u32 = 20;
+
+fn build_model() -> (Model, Vec<Id<Value>>) {
+    let mut model = Model::new();
+
+    let mut previous: Vec<_> = (0..WIDTH)
+        .map(|i| model.add_value(format!("v0_{}", i), Value::new(i as i32)))
+        .collect();
+    let roots = previous.clone();
+
+    for layer in 1..LAYERS {
+        let current: Vec<_> = (0..WIDTH)
+            .map(|i| model.add_value(format!("v{}_{}", layer, i), Value::new(0)))
+            .collect();
+        for (i, &id) in current.iter().enumerate() {
+            let left = previous[i];
+            let right = previous[(i + 1) % WIDTH];
+            model.add_dependency(id, Calculation::from(left) + right);
+        }
+        previous = current;
+    }
+
+    (model, roots)
+}
+
+fn measure(name: &str, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let average = start.elapsed() / ITERATIONS;
+    println!("{:<24} {:>12?}", name, average.max(Duration::from_nanos(1)));
+}
+
+fn main() {
+    let (model, roots) = build_model();
+    println!("{} values", LAYERS * WIDTH);
+
+    measure("new character", || {
+        Character::new(&model);
+    });
+
+    let mut character = Character::new(&model);
+    let mut base = 0;
+    measure("set base of one root", || {
+        base += 1;
+        character.set_base(roots[0], base);
+    });
+    measure("set base of all roots", || {
+        base += 1;
+        for &root in &roots {
+            character.set_base(root, base);
+        }
+    });
+    measure("batch of all roots", || {
+        base += 1;
+        character
+            .batch(|tx| {
+                for &root in &roots {
+                    tx.set_base(root, base);
+                }
+                Ok(())
+            })
+            .unwrap();
+    });
+}
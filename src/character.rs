@@ -1,14 +1,38 @@
 mod character_inventory;
 mod character_item;
+mod character_resource;
 mod character_value;
+mod effect;
+mod error;
+mod events;
+mod explain;
+mod history;
+mod item_state;
+mod recovery;
+mod roll;
+mod save;
+mod transaction;
 
 use self::character_inventory::*;
 use self::character_item::*;
+use self::character_resource::*;
 use self::character_value::*;
-
-use crate::model::{Calculation, Choice, Id, Inventory, Item, Model, Value};
-use std::collections::HashSet;
-use std::convert::TryFrom;
+use self::events::Observers;
+use self::history::History;
+use self::transaction::Change;
+
+pub use self::effect::Duration;
+pub use self::error::CharacterError;
+pub use self::events::{Event, Subscription};
+pub use self::explain::{DependencyStep, Explanation, ModificationSource, ModificationStep};
+pub use self::item_state::ItemState;
+pub use self::save::LoadError;
+pub use self::transaction::Transaction;
+
+use crate::model::{
+    Calculation, Choice, Id, Inventory, Item, Load, Model, Node, Resource, Slot, Value,
+};
+use std::{collections::BTreeSet, convert::TryFrom};
 
 /// Points to the inventory of an item.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -21,6 +45,15 @@ pub struct Character<'a> {
     inventories: Vec<CharacterInventory>,
     items: Vec<CharacterItem>,
     values: Vec<CharacterValue>,
+    resources: Vec<CharacterResource>,
+    /// Active temporary effects, see [`Character::apply_effect`].
+    effects: Vec<(Id<Item>, Duration)>,
+    observers: Observers,
+    /// Ranks of values and conditions that need to be recomputed.
+    dirty: BTreeSet<usize>,
+    /// Resources that were reduced to a lower maximum during recomputation.
+    clamped: Vec<Change>,
+    history: History,
 }
 
 impl Character<'_> {
@@ -37,46 +70,60 @@ impl Character<'_> {
             inventories: model
                 .main_inventory()
                 .into_iter()
-                .map(CharacterInventory::new)
+                .map(|id| CharacterInventory::new(id, None))
                 .collect(),
             items: model
                 .items()
                 .iter()
-                .map(|(_, item)| CharacterItem::new(item.has_inventory))
+                .map(|_| CharacterItem::with_count(0))
+                .collect(),
+            resources: model
+                .resources()
+                .iter()
+                .map(|_| CharacterResource::new())
                 .collect(),
+            effects: Vec::new(),
+            observers: Observers::default(),
+            dirty: BTreeSet::new(),
+            clamped: Vec::new(),
+            history: History::default(),
         };
 
-        result.update_all_values();
+        result.update_all();
         result
     }
 
-    fn update_all_values(&mut self) {
-        let mut todo: Vec<_> = self.model.values().iter().collect();
-        let mut done = HashSet::new();
+    /// Recompute all values, conditions and resource maximums from the base state.
+    fn update_all(&mut self) {
+        for &node in self.model.order().nodes() {
+            self.update_node(node);
+        }
+        self.clamped.clear();
+    }
 
-        while let Some((id, value)) = todo.pop() {
-            let ok = if value.dependencies.is_empty() {
-                self.apply_modifications(id);
-                true
-            } else if value
-                .dependencies
-                .iter()
-                .map(|dep| dep.values())
-                .flatten()
-                .all(|dep| done.contains(&dep))
-            {
-                self.apply_dependencies(id);
-                self.apply_modifications(id);
-                true
-            } else {
-                false
-            };
-
-            if ok {
-                done.insert(id);
-                for dependent in &value.dependents {
-                    todo.push((*dependent, self.model.values().get(*dependent)));
-                }
+    /// Recompute a value, condition or resource maximum. Returns true if dependent nodes need
+    /// to be recomputed.
+    fn update_node(&mut self, node: Node) -> bool {
+        match node {
+            Node::Value(id) => self.update_value(id),
+            Node::Item(id) => self.update_condition(id),
+            Node::Resource(id) => self.update_resource(id),
+        }
+    }
+
+    /// Schedule a value or condition for recomputation.
+    fn mark(&mut self, node: Node) {
+        self.dirty.insert(self.model.order().rank(node));
+    }
+
+    /// Recompute scheduled nodes in topological order. Nodes are only scheduled when one of
+    /// their inputs actually changed, so every node is computed at most once.
+    fn recompute(&mut self) {
+        let order = self.model.order();
+
+        while let Some(rank) = self.dirty.pop_first() {
+            if self.update_node(order.nodes()[rank]) {
+                self.dirty.extend(order.successors(rank));
             }
         }
     }
@@ -105,6 +152,21 @@ impl Character<'_> {
         calc.get(&calc.values().map(|id| self.get(id)).collect::<Vec<_>>())
     }
 
+    /// Evaluate a calculation, reading `current` for the value `id`.
+    fn eval_with(&self, calc: &Calculation, id: Id<Value>, current: i32) -> i32 {
+        let values: Vec<_> = calc
+            .values()
+            .map(|value| {
+                if value == id {
+                    current
+                } else {
+                    self.get(value)
+                }
+            })
+            .collect();
+        calc.get(&values)
+    }
+
     /// Get a value
     pub fn get(&self, id: Id<Value>) -> i32 {
         self.value(id).actual
@@ -112,43 +174,293 @@ impl Character<'_> {
 
     /// Change a base value
     pub fn set_base(&mut self, id: Id<Value>, new: i32) {
-        let value = self.value_mut(id);
+        let mut transaction = Transaction::new(self);
+        transaction.set_base(id, new);
+        transaction.commit();
+    }
+
+    /// Get the index of the active selection of a choice.
+    pub fn selection(&self, id: Id<Choice>) -> usize {
+        self.choice(id) as usize
+    }
+
+    /// Make a different selection for a choice.
+    pub fn select(&mut self, id: Id<Choice>, index: usize) -> Result<(), CharacterError> {
+        self.batch(|transaction| transaction.select(id, index))
+    }
 
-        let old = value.base;
-        if new == old {
-            return;
+    /// Get the main inventory of the character, if the model has one.
+    pub fn main_inventory(&self) -> Option<ItemInventory> {
+        if self.inventories.is_empty() {
+            None
+        } else {
+            Some(ItemInventory(0))
         }
+    }
 
-        value.base = new;
-        self.update_value(id);
+    fn inventory_index(inventory: Option<ItemInventory>) -> usize {
+        inventory.map_or(0, |inventory| inventory.0)
     }
 
-    /// Store an item into an inventory. Returns the amount that could not fit.
-    pub fn store(&mut self, inventory: Option<Id<Inventory>>, item: Id<Item>, amount: u16) -> u16 {
-        let inventory = inventory.unwrap_or_else(|| Id::new(0)).0;
+    /// Get an inventory, or `None` for the main inventory if the model has none.
+    fn inventory(&self, inventory: Option<ItemInventory>) -> Option<&CharacterInventory> {
+        self.inventories.get(Self::inventory_index(inventory))
+    }
 
-        let Inventory { capacity, slots } = &self
-            .model
-            .inventories()
-            .get(self.inventories[inventory].id());
+    /// Weight of the content of an inventory that counts towards the inventory holding it.
+    fn nested_weight(&self, index: usize, fill: u32) -> u32 {
+        let inventory = self.model.inventories().get(self.inventories[index].id());
+        (u64::from(fill) * u64::from(inventory.content_weight()) / 100) as u32
+    }
+
+    /// Recompute the fill of an inventory from its content.
+    fn compute_fill(&self, index: usize) -> u32 {
+        let items = self.model.items();
+        self.inventories[index]
+            .content
+            .iter()
+            .map(|(id, stack)| {
+                let size = items.get(*id).physical.as_ref().unwrap().size;
+                let nested: u32 = stack
+                    .inventories()
+                    .iter()
+                    .map(|inventory| {
+                        self.nested_weight(inventory.0, self.inventories[inventory.0].fill)
+                    })
+                    .sum();
+                u32::from(stack.count()) * u32::from(size) + nested
+            })
+            .sum()
+    }
+
+    /// Recompute the fill of an inventory and all inventories containing it.
+    fn update_fill(&mut self, index: usize) {
+        let mut index = Some(index);
+        while let Some(current) = index {
+            self.inventories[current].fill = self.compute_fill(current);
+            index = self.inventories[current].parent;
+        }
 
-        let capacity = capacity
+        // Containers can be moved or dropped, so any carried inventory may have changed
+        let model = self.model;
+        for (_, inventory) in model.inventories().iter() {
+            for &value in &inventory.loaded {
+                self.mark(Node::Value(value));
+            }
+        }
+    }
+
+    /// Returns true if an inventory is the main inventory or belongs to an item stored in a
+    /// carried inventory.
+    fn is_carried(&self, mut index: usize) -> bool {
+        while let Some(parent) = self.inventories[index].parent {
+            let handle = ItemInventory(index);
+            let stored = self.inventories[parent]
+                .content
+                .iter()
+                .any(|(_, stack)| stack.inventories().contains(&handle));
+            if !stored {
+                return false;
+            }
+            index = parent;
+        }
+        index == 0
+    }
+
+    /// Sum of the loads of all carried inventories of a type.
+    fn inventory_load(&self, id: Id<Inventory>, load: Load) -> i32 {
+        let total: usize = (0..self.inventories.len())
+            .filter(|&index| self.inventories[index].id() == id && self.is_carried(index))
+            .map(|index| match load {
+                Load::Fill => self.inventories[index].fill as usize,
+                Load::Slots => self.inventories[index].content.len(),
+            })
+            .sum();
+        i32::try_from(total).unwrap_or(i32::MAX)
+    }
+
+    /// Sum of the inventory loads bound to a value.
+    fn value_load(&self, id: Id<Value>) -> i32 {
+        self.model
+            .values()
+            .get(id)
+            .loads
+            .iter()
+            .map(|&(inventory, load)| self.inventory_load(inventory, load))
+            .sum()
+    }
+
+    /// Evaluate the capacity and slot limits of an inventory.
+    fn limits(&self, index: usize) -> (Option<u32>, Option<usize>) {
+        let inventory = self.model.inventories().get(self.inventories[index].id());
+        let capacity = inventory
+            .capacity
             .as_ref()
-            .map(|capacity| u32::try_from(self.eval(capacity)).unwrap());
-        let slots = slots
+            .map(|capacity| u32::try_from(self.eval(capacity)).unwrap_or(0));
+        let slots = inventory
+            .slots
             .as_ref()
-            .map(|slots| usize::try_from(self.eval(slots)).unwrap());
-        let physical = self.model.items().get(item).physical.as_ref().unwrap();
-        self.inventories[inventory].put(item, physical, amount, capacity, slots)
+            .map(|slots| usize::try_from(self.eval(slots)).unwrap_or(0));
+        (capacity, slots)
+    }
+
+    /// Returns true if adding `extra` to the fill of an inventory does not overflow any of the
+    /// inventories containing it.
+    fn fits_parents(&self, index: usize, extra: u32) -> bool {
+        let mut inner = index;
+        let mut fill = self.inventories[index].fill + extra;
+
+        while let Some(parent) = self.inventories[inner].parent {
+            let old = self.nested_weight(inner, self.inventories[inner].fill);
+            fill = self.inventories[parent].fill - old + self.nested_weight(inner, fill);
+            if matches!(self.limits(parent).0, Some(capacity) if fill > capacity) {
+                return false;
+            }
+            inner = parent;
+        }
+        true
+    }
+
+    /// Get the inventories owned by the instances of an item stored in an inventory, e.g. the
+    /// bags in a backpack.
+    pub fn containers(
+        &self,
+        inventory: Option<ItemInventory>,
+        item: Id<Item>,
+    ) -> Vec<ItemInventory> {
+        self.inventory(inventory)
+            .map(|inventory| inventory.containers(item).collect())
+            .unwrap_or_default()
+    }
+
+    /// Store an item into an inventory. Returns the amount that could not fit.
+    pub fn store(&mut self, inventory: Option<ItemInventory>, item: Id<Item>, amount: u16) -> u16 {
+        let mut transaction = Transaction::new(self);
+        let rest = transaction.put(inventory, item, amount);
+        transaction.commit();
+        rest
+    }
+
+    /// Take an item out of an inventory. Returns the amount that was missing.
+    pub fn take(&mut self, inventory: Option<ItemInventory>, item: Id<Item>, amount: u16) -> u16 {
+        let mut transaction = Transaction::new(self);
+        let missing = transaction.remove(inventory, item, amount);
+        transaction.commit();
+        missing
+    }
+
+    /// Remove all of an item from an inventory. Returns the amount that was removed.
+    pub fn drop(&mut self, inventory: Option<ItemInventory>, item: Id<Item>) -> u32 {
+        let mut transaction = Transaction::new(self);
+        let amount = transaction.drop(inventory, item);
+        transaction.commit();
+        amount
+    }
+
+    /// Move as much of an item as fits from one inventory to another. Returns the amount that
+    /// was moved, which is 0 if both inventories are the same.
+    pub fn transfer(
+        &mut self,
+        from: Option<ItemInventory>,
+        to: Option<ItemInventory>,
+        item: Id<Item>,
+        amount: u16,
+    ) -> u16 {
+        let mut transaction = Transaction::new(self);
+        let moved = transaction.transfer(from, to, item, amount);
+        transaction.commit();
+        moved
+    }
+
+    /// Get the amount of an item in an inventory.
+    pub fn amount(&self, inventory: Option<ItemInventory>, item: Id<Item>) -> u32 {
+        self.inventory(inventory)
+            .map_or(0, |inventory| inventory.amount(item))
+    }
+
+    /// Get the used capacity of an inventory, including the weighted fill of nested inventories.
+    pub fn fill(&self, inventory: Option<ItemInventory>) -> u32 {
+        self.inventory(inventory)
+            .map_or(0, |inventory| inventory.fill)
     }
 
     /// Add an item to the character.
-    pub fn equip(&mut self, id: Id<Item>) {
-        *self.items[id.0].count_mut() += 1;
+    pub fn equip(&mut self, id: Id<Item>) -> Result<(), CharacterError> {
+        self.batch(|transaction| transaction.equip(id))
+    }
 
-        for value in self.model.items().get(id).modifications.keys() {
-            self.update_value(*value);
-        }
+    /// Remove an item from the character.
+    pub fn unequip(&mut self, id: Id<Item>) -> Result<(), CharacterError> {
+        self.batch(|transaction| transaction.unequip(id))
+    }
+
+    /// Get the current amount of a resource.
+    pub fn current(&self, id: Id<Resource>) -> u32 {
+        self.resources[id.0].current
+    }
+
+    /// Get the maximum amount of a resource.
+    pub fn maximum(&self, id: Id<Resource>) -> u32 {
+        self.resources[id.0].maximum
+    }
+
+    /// Use up some of a resource. Fails without changes if there is not enough.
+    pub fn spend(&mut self, id: Id<Resource>, amount: u32) -> Result<(), CharacterError> {
+        self.batch(|transaction| transaction.spend(id, amount))
+    }
+
+    /// Refill a resource up to its maximum. Returns the amount that was restored.
+    pub fn restore(&mut self, id: Id<Resource>, amount: u32) -> u32 {
+        let mut transaction = Transaction::new(self);
+        let restored = transaction.restore(id, amount);
+        transaction.commit();
+        restored
+    }
+
+    /// Get the number of times an item is equipped, or 1 if a conditional item is active.
+    pub fn equipped(&self, id: Id<Item>) -> u16 {
+        self.item(id).count()
+    }
+
+    /// Get the number of times the modifications of an item apply: how often it is equipped
+    /// plus the number of its active effects.
+    pub fn applied(&self, id: Id<Item>) -> u16 {
+        let effects = self.effects.iter().filter(|(item, _)| *item == id).count();
+        self.equipped(id)
+            .saturating_add(u16::try_from(effects).unwrap_or(u16::MAX))
+    }
+
+    /// Get the amount of an item in the main inventory and the inventories of items carried in
+    /// it.
+    pub fn carried(&self, id: Id<Item>) -> u32 {
+        (0..self.inventories.len())
+            .filter(|&index| self.is_carried(index))
+            .map(|index| self.inventories[index].amount(id))
+            .sum()
+    }
+
+    /// Get the equipped items occupying an equipment slot.
+    pub fn occupants(&self, id: Id<Slot>) -> Vec<Id<Item>> {
+        self.model
+            .slots()
+            .get(id)
+            .items
+            .iter()
+            .copied()
+            .filter(|&item| self.equipped(item) > 0)
+            .collect()
+    }
+
+    /// Number of places of an equipment slot taken by equipped items.
+    fn occupied(&self, id: Id<Slot>) -> u32 {
+        let items = self.model.items();
+        self.occupants(id)
+            .into_iter()
+            .map(|item| {
+                let places = items.get(item).slots.iter().filter(|&&slot| slot == id);
+                places.count() as u32 * u32::from(self.equipped(item))
+            })
+            .sum()
     }
 
     fn eval_calc(&self, calc: &Calculation) -> i32 {
@@ -156,7 +468,7 @@ impl Character<'_> {
     }
 
     fn apply_dependencies(&mut self, id: Id<Value>) {
-        let mut actual = self.value(id).base;
+        let mut actual = self.value(id).base + self.value_load(id);
 
         for calc in &self.model.values().get(id).dependencies {
             actual += self.eval_calc(calc);
@@ -165,75 +477,47 @@ impl Character<'_> {
         self.value_mut(id).actual = actual;
     }
 
-    fn update_value(&mut self, id: Id<Value>) {
+    fn update_value(&mut self, id: Id<Value>) -> bool {
+        let old = self.get(id);
+        self.record_value(id, old);
         self.apply_dependencies(id);
         self.apply_modifications(id);
 
-        for dependent in &self.model.values().get(id).dependents {
-            self.update_value(*dependent);
-        }
-
-        for condition in &self.model.values().get(id).conditions {
-            self.update_condition(*condition);
-        }
-
-        // TODO: update observers
-        // NOTE: group values, only make groups observable
+        self.get(id) != old
     }
 
     fn apply_modifications(&mut self, id: Id<Value>) {
-        let mut mods: Vec<_> = self
-            .model
-            .values()
-            .get(id)
-            .modifying_items
-            .iter()
-            .filter_map(|&item| {
-                let count = self.item(item).count();
-
-                if count > 0 {
-                    Some((count, &self.model.items().get(item).modifications[&id]))
-                } else {
-                    None
-                }
-            })
-            .chain(
-                self.model
-                    .values()
-                    .get(id)
-                    .modifying_choices
-                    .iter()
-                    .filter_map(|&choice| {
-                        self.model.choices().get(choice).options[self.choice(choice) as usize]
-                            .modifications
-                            .get(&id)
-                            .map(|m| (1, m))
-                    }),
-            )
-            .collect();
-
-        // Sort by priority
-        mods.sort_unstable_by(|(_, a), (_, b)| a.priority().cmp(&b.priority()));
+        let steps = self.modification_steps(id, self.get(id));
+        if let Some(step) = steps.last() {
+            self.value_mut(id).actual = step.after;
+        }
+    }
 
-        for modification in mods {
-            let (count, modification) = modification;
+    fn update_resource(&mut self, id: Id<Resource>) -> bool {
+        let maximum = self.eval(&self.model.resources().get(id).maximum);
+        let maximum = u32::try_from(maximum).unwrap_or(0);
+        self.resources[id.0].maximum = maximum;
 
-            let calc = modification.calculation();
-            for _ in 0..count {
-                self.value_mut(id).actual = self.eval_calc(calc);
-            }
+        let current = self.resources[id.0].current;
+        if current > maximum {
+            self.record_resource(id);
+            self.resources[id.0].current = maximum;
+            self.clamped.push(Change::Current(id, current));
         }
+        false
     }
 
-    fn update_condition(&mut self, id: Id<Item>) {
-        *self.item_mut(id).count_mut() = if let Some(calc) = &self.model.items().get(id).condition {
-            self.eval(calc) as u16
-        } else {
-            unreachable!();
+    fn update_condition(&mut self, id: Id<Item>) -> bool {
+        let count = match &self.model.items().get(id).condition {
+            Some(calc) => self.eval(calc) as u16,
+            None => return false,
         };
-
-        for value in self.model.items().get(id).modifications.keys() {
-            self.update_value(*value);
+        if count == self.item(id).count() {
+            return false;
         }
+
+        self.record_item(id);
+        *self.item_mut(id).count_mut() = count;
+        true
     }
 }
@@ -1,17 +1,22 @@
-use super::CharacterItem;
+use super::{CharacterItem, ItemInventory, ItemState};
 use crate::model::{Id, Inventory, Item, Physical};
 use std::{cmp::min, convert::TryFrom};
 
+#[derive(Clone)]
 pub struct CharacterInventory {
     id: Id<Inventory>,
+    /// Inventory holding the item that owns this one, if any.
+    pub parent: Option<usize>,
     pub content: Vec<(Id<Item>, CharacterItem)>,
+    /// Size of the content, including the weighted fill of nested inventories.
     pub fill: u32,
 }
 
 impl CharacterInventory {
-    pub fn new(id: Id<Inventory>) -> Self {
+    pub fn new(id: Id<Inventory>, parent: Option<usize>) -> Self {
         Self {
             id,
+            parent,
             content: Vec::new(),
             fill: 0,
         }
@@ -23,18 +28,21 @@ impl CharacterInventory {
 
     /// Clamp `amount` to the maximum amount of `item` that will still fit.
     fn limit_fill(&self, physical: &Physical, amount: u16, capacity: Option<u32>) -> u16 {
-        if let Some(capacity) = capacity {
-            let capacity = u16::try_from(capacity - self.fill).unwrap_or(std::u16::MAX);
-            min(amount, capacity / physical.size)
-        } else {
-            amount
+        match capacity {
+            Some(capacity) if physical.size > 0 => {
+                let capacity =
+                    u16::try_from(capacity.saturating_sub(self.fill)).unwrap_or(u16::MAX);
+                min(amount, capacity / physical.size)
+            }
+            _ => amount,
         }
     }
 
-    /// Attempt to fill non-full stacks with àmount` of `item`. Returns number of items left.
-    fn fill_stacks(&mut self, id: Id<Item>, mut amount: u16, limit: u16) -> u16 {
+    /// Attempt to fill non-full stacks in the same state with àmount` of `item`. Returns number
+    /// of items left.
+    fn fill_stacks(&mut self, id: Id<Item>, state: &ItemState, mut amount: u16, limit: u16) -> u16 {
         for (slot_id, existing) in &mut self.content {
-            if *slot_id == id {
+            if *slot_id == id && existing.state() == state {
                 let space = limit - existing.count();
                 let usage = min(space, amount);
 
@@ -50,29 +58,92 @@ impl CharacterInventory {
     fn create_stacks(
         &mut self,
         id: Id<Item>,
+        state: &ItemState,
         mut amount: u16,
         limit: u16,
         slot_count: Option<usize>,
     ) -> u16 {
         while amount > 0 {
-            if slot_count.map_or(false, |slots| self.content.len() == slots) {
+            if slot_count == Some(self.content.len()) {
                 break;
             }
 
             let usage = min(limit, amount);
 
-            self.content.push((id, CharacterItem::with_count(usage)));
+            self.content
+                .push((id, CharacterItem::stack(usage, state.clone())));
             amount -= usage;
         }
 
         amount
     }
 
-    /// Attempt to put `amount` of `item` into this inventory. Returns number of items that could not fit.
+    /// Inventories owned by the instances of `item` in this inventory.
+    pub fn containers(&self, id: Id<Item>) -> impl Iterator<Item = ItemInventory> + '_ {
+        self.content
+            .iter()
+            .filter(move |(slot_id, _)| *slot_id == id)
+            .flat_map(|(_, stack)| stack.inventories().iter().copied())
+    }
+
+    /// Total amount of `item` in this inventory.
+    pub fn amount(&self, id: Id<Item>) -> u32 {
+        self.content
+            .iter()
+            .filter(|(slot_id, _)| *slot_id == id)
+            .map(|(_, stack)| u32::from(stack.count()))
+            .sum()
+    }
+
+    /// Position of the `stack`-th stack of `item` in the content.
+    pub fn position(&self, id: Id<Item>, stack: usize) -> Option<usize> {
+        self.content
+            .iter()
+            .enumerate()
+            .filter(|(_, (slot_id, _))| *slot_id == id)
+            .nth(stack)
+            .map(|(position, _)| position)
+    }
+
+    /// Attempt to remove `amount` of `item`, starting with the last stack. Returns the removed
+    /// instances.
+    pub(crate) fn remove(
+        &mut self,
+        id: Id<Item>,
+        physical: &Physical,
+        mut amount: u16,
+    ) -> Vec<CharacterItem> {
+        let mut removed = Vec::new();
+
+        for (slot_id, existing) in self.content.iter_mut().rev() {
+            if *slot_id == id && amount > 0 {
+                let usage = min(existing.count(), amount);
+
+                *existing.count_mut() -= usage;
+                amount -= usage;
+                removed.push(CharacterItem::stack(usage, existing.state().clone()));
+            }
+        }
+        self.content.retain(|(_, stack)| stack.count() > 0);
+
+        let count: u32 = removed.iter().map(|stack| u32::from(stack.count())).sum();
+        self.fill -= count * u32::from(physical.size);
+        removed
+    }
+
+    /// Add a stack without merging it into existing ones.
+    pub(crate) fn push_stack(&mut self, id: Id<Item>, stack: CharacterItem, physical: &Physical) {
+        self.fill += u32::from(stack.count()) * u32::from(physical.size);
+        self.content.push((id, stack));
+    }
+
+    /// Attempt to put `amount` of `item` in the given state into this inventory. Returns number
+    /// of items that could not fit.
     pub(crate) fn put(
         &mut self,
         id: Id<Item>,
         physical: &Physical,
+        state: &ItemState,
         amount: u16,
         capacity: Option<u32>,
         slot_count: Option<usize>,
@@ -82,8 +153,8 @@ impl CharacterInventory {
 
         let stack_size: u16 = physical.stack_size.into();
 
-        let to_put = self.fill_stacks(id, to_put, stack_size);
-        let to_put = self.create_stacks(id, to_put, stack_size, slot_count);
+        let to_put = self.fill_stacks(id, state, to_put, stack_size);
+        let to_put = self.create_stacks(id, state, to_put, stack_size, slot_count);
 
         let remainder = remainder + to_put;
         self.fill += u32::from(amount - remainder) * u32::from(physical.size);
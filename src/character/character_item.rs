@@ -1,54 +1,53 @@
-use super::ItemInventory;
-// use std::convert::TryFrom;
-use crate::model::{Id, Inventory};
+use super::{ItemInventory, ItemState};
 
+#[derive(Clone)]
 pub struct CharacterItem {
     inventories: Option<Vec<ItemInventory>>,
     count: u16,
+    state: ItemState,
 }
 
 impl CharacterItem {
-    pub fn new(has_inventory: Option<Id<Inventory>>) -> Self {
-        Self {
-            inventories: has_inventory.map(|_| Vec::new()),
-            count: 0,
-        }
+    pub fn with_count(count: u16) -> Self {
+        Self::stack(count, ItemState::default())
     }
 
-    pub fn with_count(count: u16) -> Self {
+    /// Stack of instances that share the same state.
+    pub fn stack(count: u16, state: ItemState) -> Self {
         Self {
             inventories: None,
             count,
+            state,
+        }
+    }
+
+    /// Single stored instance of an item that owns an inventory.
+    pub fn container(inventory: ItemInventory, state: ItemState) -> Self {
+        Self {
+            inventories: Some(vec![inventory]),
+            count: 1,
+            state,
         }
     }
 
-    // pub fn inventory(&self, idx: u16) -> Option<ItemInventory> {
-    //     self.inventories.as_ref().map(|inventories| inventories[idx as usize])
-    // }
+    /// Inventories owned by the instances in this stack.
+    pub fn inventories(&self) -> &[ItemInventory] {
+        self.inventories.as_deref().unwrap_or_default()
+    }
 
     pub fn count(&self) -> u16 {
         self.count
     }
 
     pub fn count_mut(&mut self) -> &mut u16 {
-        assert!(self.inventories.is_none());
         &mut self.count
     }
 
-    // pub fn push(&mut self, item: ItemInventory) -> u16 {
-    //     let inventories = self.inventories.as_mut().unwrap();
-    //
-    //     let idx = u16::try_from(inventories.len()).unwrap();
-    //     inventories.push(item);
-    //     self.count += 1;
-    //
-    //     idx
-    // }
-    //
-    // pub fn remove(&mut self, idx: u16) -> ItemInventory {
-    //     let inventories = self.inventories.as_mut().unwrap();
-    //
-    //     self.count -= 1;
-    //     inventories.remove(idx as usize)
-    // }
+    pub fn state(&self) -> &ItemState {
+        &self.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut ItemState {
+        &mut self.state
+    }
 }
@@ -0,0 +1,14 @@
+pub struct CharacterResource {
+    pub current: u32,
+    pub maximum: u32,
+}
+
+impl CharacterResource {
+    /// Create a full resource, the current amount is clamped once the maximum is computed.
+    pub fn new() -> Self {
+        Self {
+            current: u32::MAX,
+            maximum: 0,
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use super::{Change, Character, CharacterError, Transaction};
+use crate::model::{Id, Item, Model, Recovery};
+
+/// How long a temporary effect lasts, see [`Character::apply_effect`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Duration {
+    /// Expires after a number of rounds, e.g. "until the end of your next turn" is one round.
+    Rounds(u32),
+    /// Expires after a number of minutes, see [`Model::rounds_per_minute`].
+    Minutes(u32),
+    /// Expires when a recovery event is executed.
+    UntilEvent(Id<Recovery>),
+}
+
+impl Duration {
+    /// Convert minutes to rounds.
+    fn normalize(self, model: &Model) -> Self {
+        match self {
+            Self::Minutes(minutes) => {
+                Self::Rounds(minutes.saturating_mul(model.rounds_per_minute()))
+            }
+            duration => duration,
+        }
+    }
+}
+
+impl Character<'_> {
+    /// Apply an item as a temporary effect, e.g. a spell or a condition. The modifications of
+    /// the item apply one more time until the effect expires, independent of equipping it, see
+    /// [`Character::applied`].
+    pub fn apply_effect(
+        &mut self,
+        item: Id<Item>,
+        duration: Duration,
+    ) -> Result<(), CharacterError> {
+        self.batch(|transaction| transaction.apply_effect(item, duration))
+    }
+
+    /// Let time pass, expiring effects that run out.
+    pub fn advance_time(&mut self, rounds: u32) {
+        let mut transaction = Transaction::new(self);
+        transaction.advance_time(rounds);
+        transaction.commit();
+    }
+
+    /// Active temporary effects with their remaining duration, in the order they were applied.
+    /// Remaining minutes are given in rounds.
+    pub fn effects(&self) -> &[(Id<Item>, Duration)] {
+        &self.effects
+    }
+}
+
+impl Transaction<'_, '_> {
+    /// Replace the list of active effects.
+    fn set_effects(&mut self, effects: Vec<(Id<Item>, Duration)>) {
+        if effects != self.character.effects {
+            self.apply(Change::Effects(effects));
+        }
+    }
+
+    /// Remove effects.
+    fn expire(&mut self, expired: impl Fn(&Duration) -> bool) {
+        let active = self
+            .character
+            .effects
+            .iter()
+            .copied()
+            .filter(|(_, duration)| !expired(duration))
+            .collect();
+        self.set_effects(active);
+    }
+
+    /// Apply an item as a temporary effect.
+    pub fn apply_effect(
+        &mut self,
+        item: Id<Item>,
+        duration: Duration,
+    ) -> Result<(), CharacterError> {
+        if self.character.model.items().get(item).condition.is_some() {
+            return Err(CharacterError::Conditional(item));
+        }
+
+        let duration = duration.normalize(self.character.model);
+        if duration == Duration::Rounds(0) {
+            return Ok(());
+        }
+
+        let mut effects = self.character.effects.clone();
+        effects.push((item, duration));
+        self.set_effects(effects);
+        Ok(())
+    }
+
+    /// Let time pass, expiring effects that run out.
+    pub fn advance_time(&mut self, rounds: u32) {
+        if rounds == 0 {
+            return;
+        }
+
+        self.expire(|duration| matches!(duration, Duration::Rounds(left) if *left <= rounds));
+        let effects = self
+            .character
+            .effects
+            .iter()
+            .map(|&(item, duration)| match duration {
+                Duration::Rounds(left) => (item, Duration::Rounds(left - rounds)),
+                duration => (item, duration),
+            })
+            .collect();
+        self.set_effects(effects);
+    }
+
+    /// Expire all effects that last until a recovery event.
+    pub(crate) fn expire_until(&mut self, recovery: Id<Recovery>) {
+        self.expire(|duration| *duration == Duration::UntilEvent(recovery));
+    }
+}
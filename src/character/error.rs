@@ -0,0 +1,89 @@
+use crate::model::{Choice, Id, Item, Resource, Slot};
+use std::{error::Error, fmt};
+
+/// Error when changing a character.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CharacterError {
+    /// The choice has no selection with this index.
+    InvalidSelection {
+        /// The choice to select from.
+        choice: Id<Choice>,
+        /// The requested selection.
+        index: usize,
+    },
+    /// The item is not equipped.
+    NotEquipped(Id<Item>),
+    /// The item is applied based on its condition and can not be changed directly.
+    Conditional(Id<Item>),
+    /// Not all of the items fit into the inventory.
+    DoesNotFit {
+        /// The item to store.
+        item: Id<Item>,
+        /// The amount that did not fit.
+        rest: u16,
+    },
+    /// The inventory does not contain enough of the item.
+    NotEnough {
+        /// The item to take.
+        item: Id<Item>,
+        /// The amount that was missing.
+        missing: u16,
+    },
+    /// The resource does not have enough left.
+    Insufficient {
+        /// The resource to spend.
+        resource: Id<Resource>,
+        /// The amount that was missing.
+        missing: u32,
+    },
+    /// The model has no recovery event with this id string.
+    UnknownEvent(String),
+    /// The item is physical, but no more of it are carried than already equipped.
+    NotCarried(Id<Item>),
+    /// The instance state has properties the item does not have or exceeds their maximums.
+    InvalidState(Id<Item>),
+    /// An equipment slot the item needs is already full.
+    SlotOccupied {
+        /// The item to equip.
+        item: Id<Item>,
+        /// The full slot.
+        slot: Id<Slot>,
+    },
+}
+
+impl fmt::Display for CharacterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidSelection { choice, index } => {
+                write!(f, "choice {:?} has no selection {}", choice, index)
+            }
+            Self::NotEquipped(item) => write!(f, "item {:?} is not equipped", item),
+            Self::Conditional(item) => write!(f, "item {:?} is applied conditionally", item),
+            Self::DoesNotFit { item, rest } => {
+                write!(
+                    f,
+                    "{} of item {:?} do not fit into the inventory",
+                    rest, item
+                )
+            }
+            Self::NotEnough { item, missing } => {
+                write!(
+                    f,
+                    "{} of item {:?} are missing from the inventory",
+                    missing, item
+                )
+            }
+            Self::Insufficient { resource, missing } => {
+                write!(f, "resource {:?} is missing {}", resource, missing)
+            }
+            Self::UnknownEvent(id) => write!(f, "unknown recovery event `{}`", id),
+            Self::NotCarried(item) => write!(f, "item {:?} is not carried", item),
+            Self::InvalidState(item) => write!(f, "invalid state for item {:?}", item),
+            Self::SlotOccupied { item, slot } => {
+                write!(f, "slot {:?} is full, can not equip item {:?}", slot, item)
+            }
+        }
+    }
+}
+
+impl Error for CharacterError {}
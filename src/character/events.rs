@@ -0,0 +1,209 @@
+use super::{Character, ItemInventory};
+use crate::model::{Group, Id, Item, Resource, Value};
+use std::collections::BTreeMap;
+
+/// Change of a character, delivered to subscriptions created with [`Character::subscribe`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The actual number of a value changed.
+    Value {
+        /// The changed value.
+        id: Id<Value>,
+        /// Actual number before the change.
+        old: i32,
+        /// Actual number after the change.
+        new: i32,
+    },
+    /// The number of times an item applies changed, e.g. because it was equipped, an effect
+    /// expired or a conditional item toggled, see [`Character::applied`].
+    Item {
+        /// The changed item.
+        id: Id<Item>,
+        /// Count before the change.
+        old: u16,
+        /// Count after the change.
+        new: u16,
+    },
+    /// The amount of an item in an inventory changed.
+    Inventory {
+        /// The changed inventory.
+        inventory: ItemInventory,
+        /// The item that was added or removed.
+        item: Id<Item>,
+        /// Amount before the change.
+        old: u32,
+        /// Amount after the change.
+        new: u32,
+    },
+    /// The current amount of a resource changed.
+    Resource {
+        /// The changed resource.
+        id: Id<Resource>,
+        /// Current amount before the change.
+        old: u32,
+        /// Current amount after the change.
+        new: u32,
+    },
+    /// A resource was used up completely. Follows the [`Event::Resource`] of the change.
+    Depleted(Id<Resource>),
+}
+
+/// Handle for a subscription created with [`Character::subscribe`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Subscription(usize);
+
+struct Observer {
+    group: Option<Id<Group>>,
+    events: Vec<Event>,
+}
+
+/// Subscriptions and the state before the current change, keyed by id index.
+#[derive(Default)]
+pub(crate) struct Observers {
+    observers: Vec<Option<Observer>>,
+
+    values: BTreeMap<usize, i32>,
+    items: BTreeMap<usize, u16>,
+    inventories: BTreeMap<(usize, usize), u32>,
+    resources: BTreeMap<usize, u32>,
+}
+
+impl Character<'_> {
+    /// Start collecting events. Only events concerning values, items and resources of `group`
+    /// are collected, or all events if no group is given.
+    pub fn subscribe(&mut self, group: Option<Id<Group>>) -> Subscription {
+        let observer = Some(Observer {
+            group,
+            events: Vec::new(),
+        });
+
+        let observers = &mut self.observers.observers;
+        match observers.iter().position(Option::is_none) {
+            Some(idx) => {
+                observers[idx] = observer;
+                Subscription(idx)
+            }
+            None => {
+                observers.push(observer);
+                Subscription(observers.len() - 1)
+            }
+        }
+    }
+
+    /// Stop collecting events for a subscription.
+    pub fn unsubscribe(&mut self, subscription: Subscription) {
+        self.observers.observers[subscription.0] = None;
+    }
+
+    /// Take all events collected for a subscription since the last poll.
+    pub fn poll(&mut self, subscription: Subscription) -> Vec<Event> {
+        self.observers.observers[subscription.0]
+            .as_mut()
+            .map(|observer| std::mem::take(&mut observer.events))
+            .unwrap_or_default()
+    }
+
+    fn observed(&self) -> bool {
+        self.observers.observers.iter().any(Option::is_some)
+    }
+
+    /// Remember the actual number of a value before it changes.
+    pub(crate) fn record_value(&mut self, id: Id<Value>, old: i32) {
+        if self.observed() {
+            self.observers.values.entry(id.0).or_insert(old);
+        }
+    }
+
+    /// Remember the count of an item before it changes.
+    pub(crate) fn record_item(&mut self, id: Id<Item>) {
+        if self.observed() {
+            let old = self.applied(id);
+            self.observers.items.entry(id.0).or_insert(old);
+        }
+    }
+
+    /// Remember the amount of an item in an inventory before it changes.
+    pub(crate) fn record_inventory(&mut self, inventory: usize, item: Id<Item>) {
+        if self.observed() {
+            let old = self.inventories[inventory].amount(item);
+            self.observers
+                .inventories
+                .entry((inventory, item.0))
+                .or_insert(old);
+        }
+    }
+
+    /// Remember the current amount of a resource before it changes.
+    pub(crate) fn record_resource(&mut self, id: Id<Resource>) {
+        if self.observed() {
+            let old = self.current(id);
+            self.observers.resources.entry(id.0).or_insert(old);
+        }
+    }
+
+    /// Turn the recorded changes into events for all matching subscriptions.
+    pub(crate) fn flush_events(&mut self) {
+        let mut events = Vec::new();
+
+        for (id, old) in std::mem::take(&mut self.observers.values) {
+            let id = Id::new(id);
+            let new = self.get(id);
+            if old != new {
+                events.push(Event::Value { id, old, new });
+            }
+        }
+
+        for (id, old) in std::mem::take(&mut self.observers.items) {
+            let id = Id::new(id);
+            let new = self.applied(id);
+            if old != new {
+                events.push(Event::Item { id, old, new });
+            }
+        }
+
+        for ((inventory, item), old) in std::mem::take(&mut self.observers.inventories) {
+            let item = Id::new(item);
+            let new = self.inventories[inventory].amount(item);
+            if old != new {
+                let inventory = ItemInventory(inventory);
+                events.push(Event::Inventory {
+                    inventory,
+                    item,
+                    old,
+                    new,
+                });
+            }
+        }
+
+        for (id, old) in std::mem::take(&mut self.observers.resources) {
+            let id = Id::new(id);
+            let new = self.current(id);
+            if old != new {
+                events.push(Event::Resource { id, old, new });
+                if new == 0 {
+                    events.push(Event::Depleted(id));
+                }
+            }
+        }
+
+        let groups = self.model.groups();
+        for observer in self.observers.observers.iter_mut().flatten() {
+            let group = observer.group.map(|id| groups.get(id));
+
+            observer.events.extend(
+                events
+                    .iter()
+                    .filter(|event| match (group, event) {
+                        (None, _) => true,
+                        (Some(group), Event::Value { id, .. }) => group.contains_value(*id),
+                        (Some(group), Event::Item { id, .. }) => group.contains_item(*id),
+                        (Some(group), Event::Inventory { item, .. }) => group.contains_item(*item),
+                        (Some(group), Event::Resource { id, .. } | Event::Depleted(id)) => {
+                            group.contains_resource(*id)
+                        }
+                    })
+                    .cloned(),
+            );
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use super::Character;
+use crate::model::{Calculation, Choice, Id, Item, Modification, Stacking, Value};
+
+/// Breakdown of how the actual number of a value was computed, see [`Character::explain`].
+#[derive(Clone, Debug)]
+pub struct Explanation<'a> {
+    /// Base value set on the character.
+    pub base: i32,
+    /// Summed load of the inventories bound to the value.
+    pub load: i32,
+    /// Dependencies in the order they were added to the model.
+    pub dependencies: Vec<DependencyStep<'a>>,
+    /// Modifications in the order they were applied.
+    pub modifications: Vec<ModificationStep<'a>>,
+    /// Resulting actual number.
+    pub actual: i32,
+}
+
+/// Contribution of a dependency to a value.
+#[derive(Clone, Debug)]
+pub struct DependencyStep<'a> {
+    /// The dependency calculation.
+    pub calculation: &'a Calculation,
+    /// The number added to the value.
+    pub contribution: i32,
+}
+
+/// Origin of a modification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ModificationSource {
+    /// An equipped or conditionally applied item.
+    Item(Id<Item>),
+    /// The active selection of a choice.
+    Choice(Id<Choice>),
+}
+
+/// Application of a modification to a value.
+#[derive(Clone, Debug)]
+pub struct ModificationStep<'a> {
+    /// Where the modification comes from.
+    pub source: ModificationSource,
+    /// The modification.
+    pub modification: &'a Modification,
+    /// How often the modification was applied, e.g. the number of equipped items. Zero if it
+    /// was suppressed by a higher bonus of the same type.
+    pub count: u16,
+    /// Value before the modification.
+    pub before: i32,
+    /// Value after the modification.
+    pub after: i32,
+}
+
+impl<'a> Character<'a> {
+    /// Active modifications of a value with their number of applications, in application order.
+    pub(crate) fn modifications(
+        &self,
+        id: Id<Value>,
+    ) -> Vec<(ModificationSource, u16, &'a Modification)> {
+        let model = self.model;
+        let value = model.values().get(id);
+
+        let mut mods: Vec<_> = value
+            .modifying_items
+            .iter()
+            .filter_map(|&item| {
+                let count = self.applied(item);
+
+                if count > 0 {
+                    let modification = &model.items().get(item).modifications[&id];
+                    Some((ModificationSource::Item(item), count, modification))
+                } else {
+                    None
+                }
+            })
+            .chain(value.modifying_choices.iter().filter_map(|&choice| {
+                model.choices().get(choice).options[self.choice(choice) as usize]
+                    .modifications
+                    .get(&id)
+                    .map(|m| (ModificationSource::Choice(choice), 1, m))
+            }))
+            .collect();
+
+        // Sort by priority
+        mods.sort_by_key(|(_, _, m)| m.priority());
+        mods
+    }
+
+    /// Apply the active modifications of a value to `actual`, recording every step.
+    pub(crate) fn modification_steps(
+        &self,
+        id: Id<Value>,
+        mut actual: i32,
+    ) -> Vec<ModificationStep<'a>> {
+        let mods = self.modifications(id);
+        let mut steps = Vec::with_capacity(mods.len());
+        // Indices of the modifications that won their bonus type
+        let mut winners = Vec::new();
+
+        for (index, &(source, count, modification)) in mods.iter().enumerate() {
+            let before = actual;
+            let calc = modification.calculation();
+            let step = |source, modification, count, after| ModificationStep {
+                source,
+                modification,
+                count,
+                before,
+                after,
+            };
+
+            match modification.stacking() {
+                Stacking::Stack => {
+                    for _ in 0..count {
+                        actual = self.eval_with(calc, id, actual);
+                    }
+                    steps.push(step(source, modification, count, actual));
+                }
+                Stacking::Once => {
+                    actual = self.eval_with(calc, id, actual);
+                    steps.push(step(source, modification, 1, actual));
+                }
+                Stacking::AtLeast => {
+                    actual = actual.max(self.eval_with(calc, id, actual));
+                    steps.push(step(source, modification, 1, actual));
+                }
+                Stacking::AtMost => {
+                    actual = actual.min(self.eval_with(calc, id, actual));
+                    steps.push(step(source, modification, 1, actual));
+                }
+                Stacking::Typed(_) => {
+                    let same = |m: &Modification| m.stacking() == modification.stacking();
+                    if !mods[..index].iter().any(|(_, _, m)| same(m)) {
+                        // Keep the first of the highest bonuses of this type
+                        let mut winner = (index, self.eval_with(calc, id, actual));
+                        for (other, (_, _, m)) in mods.iter().enumerate().skip(index + 1) {
+                            if same(m) {
+                                let result = self.eval_with(m.calculation(), id, before);
+                                if result > winner.1 {
+                                    winner = (other, result);
+                                }
+                            }
+                        }
+                        winners.push(winner.0);
+                    }
+
+                    // The winner applies at its own priority, the others are suppressed
+                    if winners.contains(&index) {
+                        actual = self.eval_with(calc, id, actual);
+                        steps.push(step(source, modification, 1, actual));
+                    } else {
+                        steps.push(step(source, modification, 0, actual));
+                    }
+                }
+            }
+        }
+
+        steps
+    }
+
+    /// Explain how the actual number of a value is computed from its base, inventory load,
+    /// dependencies and modifications.
+    pub fn explain(&self, id: Id<Value>) -> Explanation<'a> {
+        let base = self.value(id).base;
+        let load = self.value_load(id);
+        let mut actual = base + load;
+
+        let dependencies = self
+            .model
+            .values()
+            .get(id)
+            .dependencies
+            .iter()
+            .map(|calculation| {
+                let contribution = self.eval(calculation);
+                actual += contribution;
+                DependencyStep {
+                    calculation,
+                    contribution,
+                }
+            })
+            .collect();
+
+        let modifications = self.modification_steps(id, actual);
+        if let Some(step) = modifications.last() {
+            actual = step.after;
+        }
+
+        Explanation {
+            base,
+            load,
+            dependencies,
+            modifications,
+            actual,
+        }
+    }
+}
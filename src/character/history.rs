@@ -0,0 +1,102 @@
+use super::{transaction::Change, Character};
+use std::collections::VecDeque;
+
+/// Number of edits kept by default, see [`Character::set_history_limit`].
+const DEFAULT_LIMIT: usize = 100;
+
+/// Undo and redo stacks of a character. Every entry holds the changes that revert one edit.
+pub(crate) struct History {
+    undo: VecDeque<Vec<Change>>,
+    redo: Vec<Vec<Change>>,
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl History {
+    /// Record a new edit, which makes previously undone edits unavailable.
+    pub(crate) fn push(&mut self, changes: Vec<Change>) {
+        if changes.is_empty() {
+            return;
+        }
+
+        self.redo.clear();
+        self.undo.push_back(changes);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+}
+
+impl Character<'_> {
+    /// Apply changes in reverse order. Returns the changes that revert this.
+    fn replay(&mut self, changes: Vec<Change>) -> Vec<Change> {
+        let mut reverted: Vec<_> = changes
+            .into_iter()
+            .rev()
+            .map(|change| self.apply_change(change))
+            .collect();
+
+        self.recompute();
+        reverted.append(&mut self.clamped);
+        self.flush_events();
+        reverted
+    }
+
+    /// Revert the last edit or batch. Returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo.pop_back() {
+            Some(changes) => {
+                let redo = self.replay(changes);
+                self.history.redo.push(redo);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Repeat the last undone edit or batch. Returns false if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo.pop() {
+            Some(changes) => {
+                let undo = self.replay(changes);
+                self.history.undo.push_back(undo);
+                self.history.trim();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if there is an edit to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty()
+    }
+
+    /// Returns true if there is an undone edit to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    /// Set the number of edits that can be undone. Batches count as a single edit, a limit of
+    /// zero disables the history.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history.limit = limit;
+        self.history.trim();
+        if limit == 0 {
+            self.history.redo.clear();
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use super::{Character, CharacterError, CharacterItem, ItemInventory, Transaction};
+use crate::model::{FrontEnd, Id, Item};
+
+/// Mutable properties of stored item instances, like a wand with 3 of 7 charges or a sword
+/// renamed by its owner. Instances only stack with others in the same state.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ItemState {
+    /// Charges left, if the item has charges, see [`Item::set_charges`].
+    pub charges: Option<u32>,
+    /// Durability left, if the item has durability, see [`Item::set_durability`].
+    pub durability: Option<u32>,
+    /// Front end data of the instance, e.g. a custom name. Falls back to the one of the item.
+    pub front_end: Option<FrontEnd>,
+    /// Free form notes.
+    pub notes: String,
+}
+
+impl ItemState {
+    /// State of a new instance of an item: fully charged and undamaged.
+    pub fn new(item: &Item) -> Self {
+        Self {
+            charges: item.charges(),
+            durability: item.durability(),
+            ..Self::default()
+        }
+    }
+
+    /// Returns true if the instance properties exist on the item and do not exceed their
+    /// maximums.
+    pub fn fits(&self, item: &Item) -> bool {
+        let within = |current: Option<u32>, maximum: Option<u32>| match (current, maximum) {
+            (Some(current), Some(maximum)) => current <= maximum,
+            (current, _) => current.is_none(),
+        };
+        within(self.charges, item.charges()) && within(self.durability, item.durability())
+    }
+}
+
+impl Character<'_> {
+    /// Get the state and count of the stacks of an item in an inventory.
+    pub fn stacks(
+        &self,
+        inventory: Option<ItemInventory>,
+        item: Id<Item>,
+    ) -> Vec<(&ItemState, u16)> {
+        self.inventory(inventory)
+            .into_iter()
+            .flat_map(|inventory| &inventory.content)
+            .filter(|(id, _)| *id == item)
+            .map(|(_, stack)| (stack.state(), stack.count()))
+            .collect()
+    }
+
+    /// Change the state of one instance in a stack, see [`Transaction::set_state`].
+    pub fn set_state(
+        &mut self,
+        inventory: Option<ItemInventory>,
+        item: Id<Item>,
+        stack: usize,
+        state: ItemState,
+    ) -> Result<(), CharacterError> {
+        self.batch(|transaction| transaction.set_state(inventory, item, stack, state))
+    }
+}
+
+impl Transaction<'_, '_> {
+    /// Change the state of one instance in the `stack`-th stack of an item in an inventory, as
+    /// listed by [`Character::stacks`]. The instance joins a stack in the same state if there is
+    /// room, otherwise it needs a free slot.
+    pub fn set_state(
+        &mut self,
+        inventory: Option<ItemInventory>,
+        item: Id<Item>,
+        stack: usize,
+        state: ItemState,
+    ) -> Result<(), CharacterError> {
+        let definition = self.character.model.items().get(item);
+        if !state.fits(definition) {
+            return Err(CharacterError::InvalidState(item));
+        }
+
+        let index = Character::inventory_index(inventory);
+        let position = self
+            .character
+            .inventory(inventory)
+            .and_then(|inventory| inventory.position(item, stack))
+            .ok_or(CharacterError::NotEnough { item, missing: 1 })?;
+
+        // Limits can depend on values
+        self.recompute();
+        let (_, slots) = self.character.limits(index);
+        let stack_size = match definition.has_inventory {
+            Some(_) => 1,
+            None => definition.physical.as_ref().unwrap().stack_size.get(),
+        };
+
+        let content = &self.character.inventories[index].content;
+        let count = content[position].1.count();
+        if *content[position].1.state() == state {
+            return Ok(());
+        }
+        let joined = content.iter().position(|(id, other)| {
+            *id == item && *other.state() == state && other.count() < stack_size
+        });
+        if count > 1 && joined.is_none() && slots.is_some_and(|slots| content.len() >= slots) {
+            return Err(CharacterError::DoesNotFit { item, rest: 1 });
+        }
+
+        self.character.record_inventory(index, item);
+        let content = &mut self.inventory_mut(index).content;
+        match joined {
+            None if count == 1 => *content[position].1.state_mut() = state,
+            None => {
+                *content[position].1.count_mut() -= 1;
+                content.push((item, CharacterItem::stack(1, state)));
+            }
+            Some(joined) => {
+                *content[joined].1.count_mut() += 1;
+                *content[position].1.count_mut() -= 1;
+                if count == 1 {
+                    content.remove(position);
+                }
+            }
+        }
+
+        // The number of used slots can change
+        self.character.update_fill(index);
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use super::{Character, CharacterError, CharacterItem, Transaction};
+use crate::model::{Calculation, Id, Item, Recovery};
+use std::{convert::TryFrom, mem};
+
+impl Character<'_> {
+    /// Execute a recovery event of the model, expiring effects, refreshing resources and
+    /// recharging items.
+    pub fn recover(&mut self, id: Id<Recovery>) {
+        let mut transaction = Transaction::new(self);
+        transaction.recover(id);
+        transaction.commit();
+    }
+
+    /// Execute a recovery event by its id string, e.g. `"long_rest"`.
+    pub fn apply_event(&mut self, event: &str) -> Result<(), CharacterError> {
+        let id = self
+            .model
+            .recoveries()
+            .find(event)
+            .ok_or_else(|| CharacterError::UnknownEvent(event.to_string()))?;
+        self.recover(id);
+        Ok(())
+    }
+}
+
+impl Transaction<'_, '_> {
+    /// Execute a recovery event of the model, expiring effects that last until it, then
+    /// refreshing its resources and then recharging its items in order. Amounts are computed
+    /// right before each refresh or recharge.
+    pub fn recover(&mut self, id: Id<Recovery>) {
+        let model = self.character.model;
+        let recovery = model.recoveries().get(id);
+        self.expire_until(id);
+
+        for (resource, amount) in recovery.refreshes() {
+            let amount = self.recovered(amount);
+            self.restore(resource, amount);
+        }
+        for (item, amount) in recovery.recharges() {
+            let amount = self.recovered(amount);
+            self.recharge(item, amount);
+        }
+    }
+
+    /// Evaluate a recovered amount, `None` meaning everything.
+    fn recovered(&mut self, amount: Option<&Calculation>) -> u32 {
+        match amount {
+            Some(calc) => {
+                self.recompute();
+                u32::try_from(self.character.eval(calc)).unwrap_or(0)
+            }
+            None => u32::MAX,
+        }
+    }
+
+    /// Add charges to every stored instance of an item, up to its maximum. Stacks that end up
+    /// in the same state are merged where they fit.
+    fn recharge(&mut self, item: Id<Item>, amount: u32) {
+        let definition = self.character.model.items().get(item);
+        let (maximum, physical) = match (definition.charges(), &definition.physical) {
+            (Some(maximum), Some(physical)) => (maximum, physical),
+            _ => return,
+        };
+        // Instances of items with an inventory are never merged
+        let stack_size = match definition.has_inventory {
+            Some(_) => 1,
+            None => physical.stack_size.get(),
+        };
+        let charges = |stack: &CharacterItem| {
+            let current = stack.state().charges.unwrap_or(0);
+            Some(current.saturating_add(amount).min(maximum).max(current))
+        };
+
+        for index in 0..self.character.inventories.len() {
+            let changed = self.character.inventories[index]
+                .content
+                .iter()
+                .any(|(id, stack)| *id == item && stack.state().charges != charges(stack));
+            if !changed {
+                continue;
+            }
+
+            let inventory = self.inventory_mut(index);
+            let mut content: Vec<(Id<Item>, CharacterItem)> = Vec::new();
+            for (id, mut stack) in mem::take(&mut inventory.content) {
+                if id == item {
+                    stack.state_mut().charges = charges(&stack);
+                    let joined = content.iter_mut().find(|(other, joined)| {
+                        *other == item
+                            && joined.state() == stack.state()
+                            && joined.count() < stack_size
+                    });
+                    if let Some((_, joined)) = joined {
+                        let moved = (stack_size - joined.count()).min(stack.count());
+                        *joined.count_mut() += moved;
+                        *stack.count_mut() -= moved;
+                    }
+                }
+                if stack.count() > 0 {
+                    content.push((id, stack));
+                }
+            }
+            inventory.content = content;
+
+            // The number of used slots can change
+            self.character.update_fill(index);
+        }
+    }
+}
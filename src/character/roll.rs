@@ -0,0 +1,74 @@
+use super::{Character, ModificationSource};
+use crate::dice::{Rng, RollPart, RollResult, Term};
+use crate::model::{Id, Roll, RollModifier};
+
+impl Character<'_> {
+    /// Roll a named roll of the model, including the bonuses and advantage granted by equipped
+    /// items and active selections. Advantage and disadvantage cancel each other out.
+    pub fn roll(&self, id: Id<Roll>, rng: &mut impl Rng) -> RollResult {
+        let model = self.model;
+        let roll = model.rolls().get(id);
+
+        let items = roll.modifying_items.iter().filter_map(|&item| {
+            let count = self.applied(item);
+            let modifiers = &model.items().get(item).roll_modifiers;
+            Some((ModificationSource::Item(item), count, modifiers)).filter(|_| count > 0)
+        });
+        let choices = roll.modifying_choices.iter().map(|&choice| {
+            let selection = &model.choices().get(choice).options[self.selection(choice)];
+            (
+                ModificationSource::Choice(choice),
+                1,
+                &selection.roll_modifiers,
+            )
+        });
+
+        let mut advantage = false;
+        let mut disadvantage = false;
+        let mut bonuses = Vec::new();
+        for (source, count, modifiers) in items.chain(choices) {
+            for (_, modifier) in modifiers.iter().filter(|(roll, _)| *roll == id) {
+                match modifier {
+                    RollModifier::Bonus(calc) => {
+                        bonuses.push((Some(source), self.eval(calc) * i32::from(count)))
+                    }
+                    RollModifier::Advantage => advantage = true,
+                    RollModifier::Disadvantage => disadvantage = true,
+                }
+            }
+        }
+
+        let dice = match (advantage, disadvantage) {
+            // Groups too large to roll twice are rolled once
+            (true, false) => roll.dice.map_dice(|group| {
+                if group.can_double() {
+                    group.advantage()
+                } else {
+                    group
+                }
+            }),
+            (false, true) => roll.dice.map_dice(|group| {
+                if group.can_double() {
+                    group.disadvantage()
+                } else {
+                    group
+                }
+            }),
+            _ => roll.dice.clone(),
+        };
+        let mut result = dice.roll_with(|id| self.get(id), rng);
+
+        let bonus = roll.bonus.as_ref().map(|calc| (None, self.eval(calc)));
+        for (source, bonus) in bonus.into_iter().chain(bonuses) {
+            result.push(RollPart {
+                term: Term::Constant(bonus),
+                negative: false,
+                dice: Vec::new(),
+                total: bonus,
+                source,
+            });
+        }
+
+        result
+    }
+}
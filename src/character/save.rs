@@ -0,0 +1,438 @@
+//! Plain text save format for characters.
+//!
+//! Every line holds one entry of the form `<kind> <number> <id string>`, where the id string is
+//! the rest of the line. Empty lines and lines starting with `#` are ignored.
+//!
+//! - `base <value> <value id>`: base of a value.
+//! - `select <index> <choice id>`: selected option of a choice.
+//! - `equip <count> <item id>`: equipped count of an item. Physical items have to be stored in
+//!   a carried inventory and fit into their equipment slots, as for [`Character::equip`].
+//! - `store <count> <item id>`: a stack of items in the main inventory. Each `>` in front of
+//!   `store` nests the stack one level deeper, into the inventory of the last item stored one
+//!   level above, e.g. `>store 3 torch` after `store 1 backpack`.
+//! - `charges <amount> <item id>` and `durability <amount> <item id>`: state of the stack stored
+//!   last at the same depth, only written if it differs from a new instance.
+//! - `text <field> <text>`: custom text of the stack stored last at the same depth. Fields are
+//!   0 for the name, 1 for the short name, 2 for the description and 3 for notes. The name has
+//!   to come first. Backslashes and newlines are escaped as `\\` and `\n`. Whitespace at the
+//!   start or end of the text is escaped as `\s` for spaces, `\t` for tabs, `\r` for carriage
+//!   returns and `\u{<hex>}` otherwise. Empty texts leave out the text, e.g. `text 1`.
+//! - `current <amount> <resource id>`: current amount of a resource.
+//! - `effect <rounds> <item id>`: temporary effect with the remaining number of rounds, or 0 if
+//!   it lasts until a recovery event.
+//! - `until 0 <recovery id>`: recovery event the effect written last lasts until. Has to follow
+//!   `effect` entries with 0 rounds.
+
+use super::{Character, CharacterInventory, CharacterItem, Duration, ItemInventory, ItemState};
+use crate::model::{FrontEnd, Model};
+use std::{
+    error::Error,
+    fmt,
+    io::{self, BufRead, BufReader, Read, Write},
+};
+
+/// Error when loading a character.
+#[derive(Debug)]
+pub enum LoadError {
+    /// Reading failed.
+    Io(io::Error),
+    /// A line does not follow the save format.
+    Syntax {
+        /// Line number, starting at 1.
+        line: usize,
+    },
+    /// A line refers to an entity that does not exist in the model.
+    UnknownId {
+        /// Line number, starting at 1.
+        line: usize,
+        /// The unknown id string.
+        id: String,
+    },
+    /// A line contains a value that is not valid for the entity.
+    Invalid {
+        /// Line number, starting at 1.
+        line: usize,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{}", err),
+            Self::Syntax { line } => write!(f, "syntax error in line {}", line),
+            Self::UnknownId { line, id } => write!(f, "unknown id `{}` in line {}", id, line),
+            Self::Invalid { line } => write!(f, "invalid entry in line {}", line),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl<'a> Character<'a> {
+    /// Write base values, selections, equipped items, inventory contents, resources and
+    /// temporary effects.
+    pub fn save(&self, mut writer: impl Write) -> io::Result<()> {
+        let model = self.model;
+
+        for (id, _) in model.values().iter() {
+            let id_str = model.values().id_str(id);
+            writeln!(writer, "base {} {}", self.value(id).base, id_str)?;
+        }
+
+        for (id, _) in model.choices().iter() {
+            let id_str = model.choices().id_str(id);
+            writeln!(writer, "select {} {}", self.choice(id), id_str)?;
+        }
+
+        for (id, item) in model.items().iter() {
+            let count = self.item(id).count();
+            if item.condition.is_none() && count > 0 {
+                writeln!(writer, "equip {} {}", count, model.items().id_str(id))?;
+            }
+        }
+
+        if !self.inventories.is_empty() {
+            self.save_inventory(&mut writer, 0, 0)?;
+        }
+
+        for (id, _) in model.resources().iter() {
+            let id_str = model.resources().id_str(id);
+            writeln!(writer, "current {} {}", self.current(id), id_str)?;
+        }
+
+        for &(item, duration) in &self.effects {
+            let id_str = model.items().id_str(item);
+            match duration {
+                Duration::Rounds(rounds) => writeln!(writer, "effect {} {}", rounds, id_str)?,
+                Duration::UntilEvent(recovery) => {
+                    writeln!(writer, "effect 0 {}", id_str)?;
+                    let recovery = model.recoveries().id_str(recovery);
+                    writeln!(writer, "until 0 {}", recovery)?;
+                }
+                Duration::Minutes(_) => unreachable!("minutes are stored as rounds"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the content of an inventory, followed by the content of nested inventories.
+    fn save_inventory(
+        &self,
+        writer: &mut impl Write,
+        index: usize,
+        depth: usize,
+    ) -> io::Result<()> {
+        for (id, stack) in &self.inventories[index].content {
+            let id_str = self.model.items().id_str(*id);
+            let prefix = ">".repeat(depth);
+            writeln!(writer, "{}store {} {}", prefix, stack.count(), id_str)?;
+            let initial = ItemState::new(self.model.items().get(*id));
+            save_state(writer, &prefix, id_str, stack.state(), &initial)?;
+
+            for inventory in stack.inventories() {
+                self.save_inventory(writer, inventory.0, depth + 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load a character saved with [`Character::save`].
+    pub fn load(model: &'a Model, reader: impl Read) -> Result<Self, LoadError> {
+        let mut result = Character::new(model);
+        // Inventories that `store` entries of each nesting depth go into
+        let mut path = vec![0];
+        // Line of an `effect` entry that still needs its `until` entry
+        let mut until = None;
+        // Items of `equip` entries with their line, checked once everything is stored
+        let mut equips = Vec::new();
+
+        for (line, text) in BufReader::new(reader).lines().enumerate() {
+            let line = line + 1;
+            let text = text?;
+            let text = text.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = text.splitn(3, ' ');
+            let (kind, number, id) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(kind), Some(number), Some(id)) => (kind, number, id.trim()),
+                // Empty texts have nothing after the field
+                (Some(kind), Some(number), None) if kind.trim_start_matches('>') == "text" => {
+                    (kind, number, "")
+                }
+                _ => return Err(LoadError::Syntax { line }),
+            };
+            let number: i32 = number.parse().map_err(|_| LoadError::Syntax { line })?;
+            let unknown = || LoadError::UnknownId {
+                line,
+                id: id.to_string(),
+            };
+            let invalid = LoadError::Invalid { line };
+
+            if let Some(line) = until.filter(|_| kind != "until") {
+                return Err(LoadError::Invalid { line });
+            }
+
+            let depth = kind.len() - kind.trim_start_matches('>').len();
+            let kind = &kind[depth..];
+            let nested = ["store", "charges", "durability", "text"];
+            if depth > 0 && !nested.contains(&kind) {
+                return Err(LoadError::Syntax { line });
+            }
+
+            match kind {
+                "base" => {
+                    let id = model.values().find(id).ok_or_else(unknown)?;
+                    result.value_mut(id).base = number;
+                }
+                "select" => {
+                    let id = model.choices().find(id).ok_or_else(unknown)?;
+                    let options = model.choices().get(id).options.len();
+                    if number < 0 || number as usize >= options || number > i32::from(u16::MAX) {
+                        return Err(invalid);
+                    }
+                    result.choices[id.0] = number as u16;
+                }
+                "equip" => {
+                    let id = model.items().find(id).ok_or_else(unknown)?;
+                    if model.items().get(id).condition.is_some()
+                        || number < 0
+                        || number > i32::from(u16::MAX)
+                    {
+                        return Err(invalid);
+                    }
+                    *result.item_mut(id).count_mut() = number as u16;
+                    equips.push((id, line));
+                }
+                "store" => {
+                    let id = model.items().find(id).ok_or_else(unknown)?;
+                    let item = model.items().get(id);
+                    let index = match path.get(depth) {
+                        Some(&index) if !result.inventories.is_empty() => index,
+                        _ => return Err(invalid),
+                    };
+                    path.truncate(depth + 1);
+
+                    match (&item.physical, item.has_inventory) {
+                        (Some(physical), Some(kind)) if number == 1 => {
+                            let handle = ItemInventory(result.inventories.len());
+                            result
+                                .inventories
+                                .push(CharacterInventory::new(kind, Some(index)));
+                            let stack = CharacterItem::container(handle, ItemState::new(item));
+                            result.inventories[index].push_stack(id, stack, physical);
+                            path.push(handle.0);
+                        }
+                        (Some(physical), None)
+                            if number > 0 && number <= i32::from(physical.stack_size.get()) =>
+                        {
+                            let stack = CharacterItem::stack(number as u16, ItemState::new(item));
+                            result.inventories[index].push_stack(id, stack, physical);
+                        }
+                        _ => return Err(invalid),
+                    }
+                }
+                "charges" | "durability" | "text" => {
+                    let stack = path
+                        .get(depth)
+                        .and_then(|&index| result.inventories.get_mut(index))
+                        .and_then(|inventory| inventory.content.last_mut());
+                    let (stack_id, stack) = match stack {
+                        Some((stack_id, stack)) => (*stack_id, stack),
+                        None => return Err(invalid),
+                    };
+                    let state = stack.state_mut();
+
+                    match kind {
+                        "text" => {
+                            let text = unescape(id).ok_or(LoadError::Syntax { line })?;
+                            match (number, &mut state.front_end) {
+                                (0, front_end) => match front_end {
+                                    Some(front_end) => front_end.name = text,
+                                    None => *front_end = Some(FrontEnd::new(text)),
+                                },
+                                (1, Some(front_end)) => front_end.name_short = Some(text),
+                                (2, Some(front_end)) => front_end.description = Some(text),
+                                (3, _) => state.notes = text,
+                                _ => return Err(invalid),
+                            }
+                        }
+                        _ => {
+                            let id = model.items().find(id).ok_or_else(unknown)?;
+                            if id != stack_id || number < 0 {
+                                return Err(invalid);
+                            }
+                            match kind {
+                                "charges" => state.charges = Some(number as u32),
+                                _ => state.durability = Some(number as u32),
+                            }
+                            if !state.fits(model.items().get(id)) {
+                                return Err(invalid);
+                            }
+                        }
+                    }
+                }
+                "current" => {
+                    let id = model.resources().find(id).ok_or_else(unknown)?;
+                    if number < 0 {
+                        return Err(invalid);
+                    }
+                    result.resources[id.0].current = number as u32;
+                }
+                "effect" => {
+                    let id = model.items().find(id).ok_or_else(unknown)?;
+                    if number < 0 {
+                        return Err(invalid);
+                    }
+                    if number == 0 {
+                        until = Some(line);
+                    }
+                    result.effects.push((id, Duration::Rounds(number as u32)));
+                }
+                "until" => {
+                    let id = model.recoveries().find(id).ok_or_else(unknown)?;
+                    if until.take().is_none() || number != 0 {
+                        return Err(invalid);
+                    }
+                    result.effects.last_mut().unwrap().1 = Duration::UntilEvent(id);
+                }
+                _ => return Err(LoadError::Syntax { line }),
+            }
+        }
+
+        if let Some(line) = until {
+            return Err(LoadError::Invalid { line });
+        }
+
+        // Nested inventories are created after the ones containing them
+        for index in (0..result.inventories.len()).rev() {
+            result.inventories[index].fill = result.compute_fill(index);
+        }
+
+        // Equipped items have to be carried and fit into their slots
+        for (id, line) in equips {
+            let definition = model.items().get(id);
+            let equipped = u32::from(result.equipped(id));
+            let uncarried = definition.physical.is_some() && result.carried(id) < equipped;
+            let occupied = definition
+                .slots
+                .iter()
+                .any(|&slot| result.occupied(slot) > u32::from(model.slots().get(slot).count));
+            if uncarried || occupied {
+                return Err(LoadError::Invalid { line });
+            }
+        }
+
+        result.update_all();
+        Ok(result)
+    }
+}
+
+/// Write the parts of an instance state that differ from a new instance.
+fn save_state(
+    writer: &mut impl Write,
+    prefix: &str,
+    id_str: &str,
+    state: &ItemState,
+    initial: &ItemState,
+) -> io::Result<()> {
+    if let Some(charges) = state.charges.filter(|_| state.charges != initial.charges) {
+        writeln!(writer, "{}charges {} {}", prefix, charges, id_str)?;
+    }
+    if let Some(durability) = state
+        .durability
+        .filter(|_| state.durability != initial.durability)
+    {
+        writeln!(writer, "{}durability {} {}", prefix, durability, id_str)?;
+    }
+
+    let mut texts = Vec::new();
+    if let Some(front_end) = &state.front_end {
+        texts.push((0, Some(&front_end.name)));
+        texts.push((1, front_end.name_short.as_ref()));
+        texts.push((2, front_end.description.as_ref()));
+    }
+    texts.push((3, Some(&state.notes).filter(|notes| !notes.is_empty())));
+    for (field, text) in texts {
+        if let Some(text) = text {
+            let text = escape(text);
+            if text.is_empty() {
+                writeln!(writer, "{}text {}", prefix, field)?;
+            } else {
+                writeln!(writer, "{}text {} {}", prefix, field, text)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Escape backslashes, newlines and whitespace at the start or end, which would be trimmed.
+fn escape(text: &str) -> String {
+    let start = text.len() - text.trim_start().len();
+    let end = text.trim_end().len();
+    let mut result = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        let edge = i < start || i >= end;
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            ' ' if edge => result.push_str("\\s"),
+            '\t' if edge => result.push_str("\\t"),
+            '\r' if edge => result.push_str("\\r"),
+            c if edge && c.is_whitespace() => {
+                result.push_str(&format!("\\u{{{:x}}}", u32::from(c)))
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Reverse [`escape`], returns `None` for unknown escape sequences.
+fn unescape(text: &str) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                'n' => result.push('\n'),
+                's' => result.push(' '),
+                't' => result.push('\t'),
+                'r' => result.push('\r'),
+                '\\' => result.push('\\'),
+                'u' => {
+                    if chars.next()? != '{' {
+                        return None;
+                    }
+                    let mut hex = String::new();
+                    loop {
+                        match chars.next()? {
+                            '}' => break,
+                            c => hex.push(c),
+                        }
+                    }
+                    let code = u32::from_str_radix(&hex, 16).ok()?;
+                    result.push(char::from_u32(code)?);
+                }
+                _ => return None,
+            },
+            c => result.push(c),
+        }
+    }
+    Some(result)
+}
@@ -0,0 +1,582 @@
+use super::{
+    Character, CharacterError, CharacterInventory, CharacterItem, Duration, ItemInventory,
+    ItemState,
+};
+use crate::model::{Choice, Id, Inventory, Item, Node, Physical, Resource, Value};
+use std::{convert::TryFrom, mem, num::NonZeroU16};
+
+/// Change of the base state of a character, holding the state to restore.
+pub(crate) enum Change {
+    Base(Id<Value>, i32),
+    Selection(Id<Choice>, u16),
+    Count(Id<Item>, u16),
+    Inventory(usize, CharacterInventory),
+    /// Inventories from an index on, used to remove inventories of new containers.
+    Inventories(usize, Vec<CharacterInventory>),
+    Current(Id<Resource>, u32),
+    Effects(Vec<(Id<Item>, Duration)>),
+}
+
+/// Group of edits to a character, see [`Character::batch`].
+///
+/// Values are only recomputed when they are read or when the batch ends, and events are
+/// emitted once for the whole batch.
+pub struct Transaction<'c, 'a> {
+    pub(crate) character: &'c mut Character<'a>,
+    journal: Vec<Change>,
+}
+
+impl<'a> Character<'a> {
+    /// Apply several edits at once. If `edits` fails, all of its edits are rolled back.
+    pub fn batch<T>(
+        &mut self,
+        edits: impl FnOnce(&mut Transaction<'_, 'a>) -> Result<T, CharacterError>,
+    ) -> Result<T, CharacterError> {
+        let mut transaction = Transaction::new(self);
+        let result = edits(&mut transaction);
+
+        if result.is_err() {
+            transaction.rollback_to(0);
+        }
+        transaction.commit();
+        result
+    }
+
+    /// Replace part of the base state. Returns the change that reverts this.
+    ///
+    /// Resources are not clamped here, use [`Character::recompute`] afterwards.
+    pub(crate) fn apply_change(&mut self, change: Change) -> Change {
+        match change {
+            Change::Base(id, base) => {
+                self.mark(Node::Value(id));
+                Change::Base(id, mem::replace(&mut self.value_mut(id).base, base))
+            }
+            Change::Selection(id, index) => {
+                let old = mem::replace(&mut self.choices[id.0], index);
+
+                let options = &self.model.choices().get(id).options;
+                for selection in &[old, index] {
+                    for value in options[usize::from(*selection)].modifications.keys() {
+                        self.mark(Node::Value(*value));
+                    }
+                }
+                Change::Selection(id, old)
+            }
+            Change::Count(id, count) => {
+                self.record_item(id);
+                for value in self.model.items().get(id).modifications.keys() {
+                    self.mark(Node::Value(*value));
+                }
+                Change::Count(id, mem::replace(self.item_mut(id).count_mut(), count))
+            }
+            Change::Inventory(index, inventory) => {
+                let items: Vec<_> = self.inventories[index]
+                    .content
+                    .iter()
+                    .chain(&inventory.content)
+                    .map(|(id, _)| *id)
+                    .collect();
+                for item in items {
+                    self.record_inventory(index, item);
+                }
+                let old = mem::replace(&mut self.inventories[index], inventory);
+                self.update_fill(index);
+                Change::Inventory(index, old)
+            }
+            Change::Inventories(len, inventories) => {
+                let old = self.inventories.split_off(len);
+                self.inventories.extend(inventories);
+                Change::Inventories(len, old)
+            }
+            Change::Current(id, current) => {
+                self.record_resource(id);
+                Change::Current(id, mem::replace(&mut self.resources[id.0].current, current))
+            }
+            Change::Effects(effects) => {
+                let items: Vec<_> = self.effects.iter().chain(&effects).map(|e| e.0).collect();
+                for item in items {
+                    self.record_item(item);
+                    for value in self.model.items().get(item).modifications.keys() {
+                        self.mark(Node::Value(*value));
+                    }
+                }
+                Change::Effects(mem::replace(&mut self.effects, effects))
+            }
+        }
+    }
+}
+
+impl<'c, 'a> Transaction<'c, 'a> {
+    pub(crate) fn new(character: &'c mut Character<'a>) -> Self {
+        Self {
+            character,
+            journal: Vec::new(),
+        }
+    }
+
+    /// Recompute changed values, emit events and record the changes for undo.
+    pub(crate) fn commit(mut self) {
+        self.recompute();
+        self.character.flush_events();
+        self.character.history.push(self.journal);
+    }
+
+    /// Recompute changed values, remembering the resources clamped on the way.
+    pub(crate) fn recompute(&mut self) {
+        self.character.recompute();
+        self.journal.append(&mut self.character.clamped);
+    }
+
+    /// Revert all changes after the first `len` ones.
+    fn rollback_to(&mut self, len: usize) {
+        while self.journal.len() > len {
+            let change = self.journal.pop().unwrap();
+            self.character.apply_change(change);
+        }
+    }
+
+    pub(crate) fn apply(&mut self, change: Change) {
+        let revert = self.character.apply_change(change);
+        self.journal.push(revert);
+    }
+
+    /// Get mutable access to an inventory, remembering its previous state.
+    pub(crate) fn inventory_mut(&mut self, index: usize) -> &mut CharacterInventory {
+        let snapshot = self.character.inventories[index].clone();
+        self.journal.push(Change::Inventory(index, snapshot));
+        &mut self.character.inventories[index]
+    }
+
+    /// Get a value, recomputing it if necessary.
+    pub fn get(&mut self, id: Id<Value>) -> i32 {
+        self.recompute();
+        self.character.get(id)
+    }
+
+    /// Get the current amount of a resource, clamping it if its maximum changed.
+    pub fn current(&mut self, id: Id<Resource>) -> u32 {
+        self.recompute();
+        self.character.current(id)
+    }
+
+    /// Use up some of a resource. Fails without changes if there is not enough.
+    pub fn spend(&mut self, id: Id<Resource>, amount: u32) -> Result<(), CharacterError> {
+        let current = self.current(id);
+        if current < amount {
+            return Err(CharacterError::Insufficient {
+                resource: id,
+                missing: amount - current,
+            });
+        }
+
+        if amount > 0 {
+            self.apply(Change::Current(id, current - amount));
+        }
+        Ok(())
+    }
+
+    /// Refill a resource up to its maximum. Returns the amount that was restored.
+    pub fn restore(&mut self, id: Id<Resource>, amount: u32) -> u32 {
+        let current = self.current(id);
+        let new = current
+            .saturating_add(amount)
+            .min(self.character.maximum(id))
+            .max(current);
+
+        if new != current {
+            self.apply(Change::Current(id, new));
+        }
+        new - current
+    }
+
+    /// Change a base value.
+    pub fn set_base(&mut self, id: Id<Value>, new: i32) {
+        if self.character.value(id).base != new {
+            self.apply(Change::Base(id, new));
+        }
+    }
+
+    /// Make a different selection for a choice. Options past index `u16::MAX` can not be
+    /// selected.
+    pub fn select(&mut self, id: Id<Choice>, index: usize) -> Result<(), CharacterError> {
+        let options = &self.character.model.choices().get(id).options;
+        let selection = u16::try_from(index)
+            .ok()
+            .filter(|_| index < options.len())
+            .ok_or(CharacterError::InvalidSelection { choice: id, index })?;
+
+        if self.character.selection(id) != index {
+            self.apply(Change::Selection(id, selection));
+        }
+        Ok(())
+    }
+
+    /// Add an item to the character.
+    ///
+    /// Physical items need to be carried more often than they are already equipped. Items
+    /// occupying equipment slots also need room in each of their slots. Conditional items can
+    /// not be equipped.
+    pub fn equip(&mut self, id: Id<Item>) -> Result<(), CharacterError> {
+        let character = &*self.character;
+        let definition = character.model.items().get(id);
+        if definition.condition.is_some() {
+            return Err(CharacterError::Conditional(id));
+        }
+
+        let count = character.equipped(id) + 1;
+        if definition.physical.is_some() && character.carried(id) < u32::from(count) {
+            return Err(CharacterError::NotCarried(id));
+        }
+        let slots = &definition.slots;
+        for &slot in slots {
+            let needed = slots.iter().filter(|&&other| other == slot).count() as u32;
+            let room = u32::from(character.model.slots().get(slot).count);
+            if character.occupied(slot) + needed > room {
+                return Err(CharacterError::SlotOccupied { item: id, slot });
+            }
+        }
+
+        self.apply(Change::Count(id, count));
+        Ok(())
+    }
+
+    /// Unequip physical items until no more are equipped than carried.
+    fn unequip_uncarried(&mut self) {
+        for (item, definition) in self.character.model.items().iter() {
+            let equipped = self.character.equipped(item);
+            if equipped == 0 || definition.physical.is_none() || definition.condition.is_some() {
+                continue;
+            }
+            let carried = u16::try_from(self.character.carried(item)).unwrap_or(u16::MAX);
+            if equipped > carried {
+                self.apply(Change::Count(item, carried));
+            }
+        }
+    }
+
+    /// Remove an item from the character.
+    pub fn unequip(&mut self, id: Id<Item>) -> Result<(), CharacterError> {
+        if self.character.model.items().get(id).condition.is_some() {
+            return Err(CharacterError::Conditional(id));
+        }
+
+        match self.character.equipped(id) {
+            0 => Err(CharacterError::NotEquipped(id)),
+            count => {
+                self.apply(Change::Count(id, count - 1));
+                Ok(())
+            }
+        }
+    }
+
+    /// Store as much of an item as fits into an inventory. Returns the amount that could not fit.
+    pub(crate) fn put(
+        &mut self,
+        inventory: Option<ItemInventory>,
+        item: Id<Item>,
+        amount: u16,
+    ) -> u16 {
+        let state = ItemState::new(self.character.model.items().get(item));
+        self.put_state(inventory, item, &state, amount)
+    }
+
+    /// Store as much of an item in the given state as fits into an inventory. Returns the amount
+    /// that could not fit.
+    fn put_state(
+        &mut self,
+        inventory: Option<ItemInventory>,
+        item: Id<Item>,
+        state: &ItemState,
+        amount: u16,
+    ) -> u16 {
+        // Limits can depend on values
+        self.recompute();
+
+        let character = &*self.character;
+        let index = Character::inventory_index(inventory);
+        let definition = character.model.items().get(item);
+        let physical = match &definition.physical {
+            Some(physical) if index < character.inventories.len() => physical,
+            // Only physical items can be stored, and only if there is an inventory
+            _ => return amount,
+        };
+        let (capacity, slots) = character.limits(index);
+
+        // Every instance of an item with an inventory is a stack of its own
+        let single = Physical {
+            size: physical.size,
+            stack_size: NonZeroU16::new(1).unwrap(),
+        };
+        let physical = match definition.has_inventory {
+            Some(_) => &single,
+            None => physical,
+        };
+
+        // Inventories containing this one need to fit the added size as well
+        let size = u32::from(physical.size);
+        let mut fitting = amount;
+        if !character.fits_parents(index, u32::from(amount) * size) {
+            let (mut low, mut high) = (0, amount);
+            while high - low > 1 {
+                let mid = low + (high - low) / 2;
+                if character.fits_parents(index, u32::from(mid) * size) {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            fitting = low;
+        }
+
+        let len = self.journal.len();
+        if definition.has_inventory.is_some() {
+            // New inventories are removed after the content referring to them is restored
+            let inventories = character.inventories.len();
+            self.journal
+                .push(Change::Inventories(inventories, Vec::new()));
+        }
+
+        self.character.record_inventory(index, item);
+        let rest = self
+            .inventory_mut(index)
+            .put(item, physical, state, fitting, capacity, slots)
+            + (amount - fitting);
+        if rest == amount {
+            self.journal.truncate(len);
+            return rest;
+        }
+
+        if let Some(kind) = definition.has_inventory {
+            self.attach_inventories(index, item, kind);
+        }
+        self.character.update_fill(index);
+        rest
+    }
+
+    /// Create inventories for new instances of an item that owns an inventory.
+    fn attach_inventories(&mut self, index: usize, item: Id<Item>, kind: Id<Inventory>) {
+        let inventories = &mut self.character.inventories;
+
+        for position in 0..inventories[index].content.len() {
+            let (id, stack) = &inventories[index].content[position];
+            if *id == item && stack.inventories().is_empty() {
+                let state = stack.state().clone();
+                let handle = ItemInventory(inventories.len());
+                inventories.push(CharacterInventory::new(kind, Some(index)));
+                inventories[index].content[position].1 = CharacterItem::container(handle, state);
+            }
+        }
+    }
+
+    /// Store an item into an inventory. Fails without storing anything if it does not fit.
+    pub fn store(
+        &mut self,
+        inventory: Option<ItemInventory>,
+        item: Id<Item>,
+        amount: u16,
+    ) -> Result<(), CharacterError> {
+        let len = self.journal.len();
+        match self.put(inventory, item, amount) {
+            0 => Ok(()),
+            rest => {
+                self.rollback_to(len);
+                Err(CharacterError::DoesNotFit { item, rest })
+            }
+        }
+    }
+
+    /// Take as much of an item out of an inventory as possible, unequipping what is no longer
+    /// carried. Returns the amount that was missing.
+    pub(crate) fn remove(
+        &mut self,
+        inventory: Option<ItemInventory>,
+        item: Id<Item>,
+        amount: u16,
+    ) -> u16 {
+        let removed = self.withdraw(inventory, item, amount);
+        if !removed.is_empty() {
+            self.unequip_uncarried();
+        }
+        amount - removed.iter().map(CharacterItem::count).sum::<u16>()
+    }
+
+    /// Take as much of an item out of an inventory as possible. Returns the removed instances.
+    fn withdraw(
+        &mut self,
+        inventory: Option<ItemInventory>,
+        item: Id<Item>,
+        amount: u16,
+    ) -> Vec<CharacterItem> {
+        let index = Character::inventory_index(inventory);
+        let physical = match &self.character.model.items().get(item).physical {
+            Some(physical) if index < self.character.inventories.len() => physical,
+            _ => return Vec::new(),
+        };
+
+        self.character.record_inventory(index, item);
+        let removed = self.inventory_mut(index).remove(item, physical, amount);
+        if removed.is_empty() {
+            self.journal.pop();
+        } else {
+            self.character.update_fill(index);
+        }
+        removed
+    }
+
+    /// Take an item out of an inventory. Fails without taking anything if there is not enough.
+    pub fn take(
+        &mut self,
+        inventory: Option<ItemInventory>,
+        item: Id<Item>,
+        amount: u16,
+    ) -> Result<(), CharacterError> {
+        let len = self.journal.len();
+        match self.remove(inventory, item, amount) {
+            0 => Ok(()),
+            missing => {
+                self.rollback_to(len);
+                Err(CharacterError::NotEnough { item, missing })
+            }
+        }
+    }
+
+    /// Remove all of an item from an inventory. Returns the amount that was removed.
+    pub fn drop(&mut self, inventory: Option<ItemInventory>, item: Id<Item>) -> u32 {
+        let amount = self.character.amount(inventory, item);
+        if amount == 0 {
+            return 0;
+        }
+
+        let index = Character::inventory_index(inventory);
+        self.character.record_inventory(index, item);
+        self.inventory_mut(index)
+            .content
+            .retain(|(id, _)| *id != item);
+        self.character.update_fill(index);
+        self.unequip_uncarried();
+        amount
+    }
+
+    /// Move as much of an item as fits from one inventory to another. Returns the amount that
+    /// was moved, which is 0 if both inventories are the same. The source is left untouched if
+    /// nothing fits, or if what does not fit can not be put back. Items that are no longer
+    /// carried afterwards are unequipped.
+    pub fn transfer(
+        &mut self,
+        from: Option<ItemInventory>,
+        to: Option<ItemInventory>,
+        item: Id<Item>,
+        amount: u16,
+    ) -> u16 {
+        let source = Character::inventory_index(from);
+        let target = Character::inventory_index(to);
+        let available = self.character.amount(from, item);
+        let available = u16::try_from(available).unwrap_or(u16::MAX).min(amount);
+        if source == target || available == 0 {
+            return 0;
+        }
+
+        if self
+            .character
+            .model
+            .items()
+            .get(item)
+            .has_inventory
+            .is_some()
+        {
+            // Try the instances in the order `take` would remove them
+            let containers: Vec<_> = self.character.inventories[source]
+                .containers(item)
+                .collect();
+            let mut moved = 0;
+            for container in containers.into_iter().rev() {
+                if moved == available {
+                    break;
+                }
+                if self.move_container(source, target, item, container) {
+                    moved += 1;
+                }
+            }
+            if moved > 0 {
+                self.unequip_uncarried();
+            }
+            return moved;
+        }
+
+        // Removing first frees up space if the target is nested in the source
+        let len = self.journal.len();
+        let mut moved = 0;
+        let mut rest = Vec::new();
+        for stack in self.withdraw(from, item, available) {
+            let left = self.put_state(to, item, stack.state(), stack.count());
+            moved += stack.count() - left;
+            if left > 0 {
+                rest.push((stack, left));
+            }
+        }
+
+        // The rest may not fit back if the target weighs its content more than the source
+        let restored = moved > 0
+            && rest
+                .into_iter()
+                .all(|(stack, left)| self.put_state(from, item, stack.state(), left) == 0);
+        if !restored {
+            self.rollback_to(len);
+            return 0;
+        }
+        self.unequip_uncarried();
+        moved
+    }
+
+    /// Move a single instance of an item that owns an inventory, along with its content.
+    /// Returns false if it does not fit or if the target is part of its own inventory.
+    fn move_container(
+        &mut self,
+        source: usize,
+        target: usize,
+        item: Id<Item>,
+        container: ItemInventory,
+    ) -> bool {
+        let mut ancestor = Some(target);
+        while let Some(index) = ancestor {
+            if index == container.0 {
+                return false;
+            }
+            ancestor = self.character.inventories[index].parent;
+        }
+
+        let len = self.journal.len();
+        self.character.record_inventory(source, item);
+        let inventory = self.inventory_mut(source);
+        let position = inventory
+            .content
+            .iter()
+            .position(|(_, stack)| stack.inventories() == [container])
+            .unwrap();
+        let (_, stack) = inventory.content.remove(position);
+        self.character.update_fill(source);
+
+        // Limits can depend on values
+        self.recompute();
+        let character = &*self.character;
+        let (capacity, slots) = character.limits(target);
+        let physical = character.model.items().get(item).physical.as_ref();
+        let fill = character.inventories[container.0].fill;
+        let size = u32::from(physical.unwrap().size) + character.nested_weight(container.0, fill);
+
+        let inventory = &character.inventories[target];
+        let fits = slots.is_none_or(|slots| inventory.content.len() < slots)
+            && capacity.is_none_or(|capacity| inventory.fill + size <= capacity)
+            && character.fits_parents(target, size);
+        if !fits {
+            self.rollback_to(len);
+            return false;
+        }
+
+        self.character.record_inventory(target, item);
+        self.inventory_mut(target).content.push((item, stack));
+        self.inventory_mut(container.0).parent = Some(target);
+        self.character.update_fill(target);
+        true
+    }
+}
@@ -0,0 +1,334 @@
+//! Dice expressions like `1d20 + strength_mod + 2`, rolled for a character.
+
+mod display;
+mod parse;
+mod rng;
+
+pub use self::display::*;
+pub use self::parse::*;
+pub use self::rng::*;
+
+use crate::model::{Id, Value};
+use crate::{Character, ModificationSource};
+use std::convert::TryFrom;
+
+/// Rerolls of a single exploding die are stopped after this many.
+const MAX_EXPLOSIONS: usize = 100;
+
+/// Which dice of a group count towards the total.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Keep {
+    /// Keep the given number of highest dice.
+    Highest(u16),
+    /// Keep the given number of lowest dice.
+    Lowest(u16),
+}
+
+/// A number of dice with the same number of sides, e.g. `4d6kh3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiceGroup {
+    count: u16,
+    sides: u16,
+    keep: Option<Keep>,
+    explode: bool,
+}
+
+impl DiceGroup {
+    /// Create a group of `count` dice with `sides` sides each.
+    ///
+    /// Panics if either number is zero.
+    pub fn new(count: u16, sides: u16) -> Self {
+        assert!(count > 0 && sides > 0, "dice need a count and sides");
+        Self {
+            count,
+            sides,
+            keep: None,
+            explode: false,
+        }
+    }
+
+    /// Only count some of the dice towards the total.
+    ///
+    /// Panics if more dice should be kept than are rolled.
+    pub fn keep(mut self, keep: Keep) -> Self {
+        let (Keep::Highest(n) | Keep::Lowest(n)) = keep;
+        assert!(n <= self.count, "can not keep more dice than are rolled");
+        self.keep = Some(keep);
+        self
+    }
+
+    /// Roll the dice twice and keep the higher half, e.g. `1d20` becomes `2d20kh1`.
+    ///
+    /// Panics if twice the number of dice does not fit into a `u16`.
+    pub fn advantage(self) -> Self {
+        let keep = Keep::Highest(self.count);
+        self.doubled().keep(keep)
+    }
+
+    /// Roll the dice twice and keep the lower half, e.g. `1d20` becomes `2d20kl1`.
+    ///
+    /// Panics if twice the number of dice does not fit into a `u16`.
+    pub fn disadvantage(self) -> Self {
+        let keep = Keep::Lowest(self.count);
+        self.doubled().keep(keep)
+    }
+
+    /// Returns true if the group can be rolled with advantage or disadvantage.
+    pub(crate) fn can_double(&self) -> bool {
+        self.count.checked_mul(2).is_some()
+    }
+
+    fn doubled(self) -> Self {
+        Self {
+            count: self
+                .count
+                .checked_mul(2)
+                .expect("too many dice to roll twice"),
+            ..self
+        }
+    }
+
+    /// Roll another die whenever a die shows its highest side.
+    ///
+    /// Panics for single-sided dice.
+    pub fn explode(mut self) -> Self {
+        assert!(self.sides > 1, "single-sided dice can not explode");
+        self.explode = true;
+        self
+    }
+
+    /// Number of dice.
+    pub fn count(&self) -> u16 {
+        self.count
+    }
+
+    /// Number of sides of each die.
+    pub fn sides(&self) -> u16 {
+        self.sides
+    }
+
+    /// Which dice count towards the total.
+    pub fn kept(&self) -> Option<Keep> {
+        self.keep
+    }
+
+    /// Whether dice showing their highest side are rolled again.
+    pub fn explodes(&self) -> bool {
+        self.explode
+    }
+
+    fn roll(&self, rng: &mut impl Rng) -> Vec<DieRoll> {
+        let mut rolls: Vec<_> = (0..self.count)
+            .map(|_| {
+                let mut faces = vec![rng.die(self.sides)];
+                while self.explode
+                    && faces.last() == Some(&self.sides)
+                    && faces.len() <= MAX_EXPLOSIONS
+                {
+                    faces.push(rng.die(self.sides));
+                }
+                DieRoll { faces, kept: true }
+            })
+            .collect();
+
+        if let Some(keep) = self.keep {
+            let mut order: Vec<_> = (0..rolls.len()).collect();
+            order.sort_by_key(|&i| rolls[i].total());
+            let drop = match keep {
+                Keep::Highest(n) => &order[..rolls.len() - usize::from(n)],
+                Keep::Lowest(n) => &order[usize::from(n)..],
+            };
+            for &i in drop {
+                rolls[i].kept = false;
+            }
+        }
+
+        rolls
+    }
+}
+
+/// Part of a dice expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Term {
+    /// Dice to roll.
+    Dice(DiceGroup),
+    /// A fixed number.
+    Constant(i32),
+    /// The actual number of a character value.
+    Value(Id<Value>),
+}
+
+impl From<DiceGroup> for Term {
+    fn from(dice: DiceGroup) -> Self {
+        Self::Dice(dice)
+    }
+}
+
+impl From<i32> for Term {
+    fn from(c: i32) -> Self {
+        Self::Constant(c)
+    }
+}
+
+impl From<Id<Value>> for Term {
+    fn from(id: Id<Value>) -> Self {
+        Self::Value(id)
+    }
+}
+
+/// Sum of dice, constants and values, e.g. `1d20 + strength_mod + 2`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dice {
+    terms: Vec<(bool, Term)>,
+}
+
+impl Dice {
+    /// Create an expression with a single term.
+    pub fn new(term: impl Into<Term>) -> Self {
+        Self {
+            terms: vec![(false, term.into())],
+        }
+    }
+
+    /// Add a term.
+    pub fn plus(mut self, term: impl Into<Term>) -> Self {
+        self.terms.push((false, term.into()));
+        self
+    }
+
+    /// Subtract a term.
+    pub fn minus(mut self, term: impl Into<Term>) -> Self {
+        self.terms.push((true, term.into()));
+        self
+    }
+
+    /// Terms of the expression, with true for subtracted ones.
+    pub fn terms(&self) -> impl Iterator<Item = (bool, Term)> + '_ {
+        self.terms.iter().copied()
+    }
+
+    /// Values of the model read by the expression.
+    pub fn values(&self) -> impl Iterator<Item = Id<Value>> + '_ {
+        self.terms.iter().filter_map(|(_, term)| match term {
+            Term::Value(id) => Some(*id),
+            _ => None,
+        })
+    }
+
+    /// Change all dice groups that do not already keep only some of their dice.
+    pub(crate) fn map_dice(&self, f: impl Fn(DiceGroup) -> DiceGroup) -> Self {
+        let terms = self
+            .terms
+            .iter()
+            .map(|&(negative, term)| match term {
+                Term::Dice(group) if group.keep.is_none() => (negative, Term::Dice(f(group))),
+                term => (negative, term),
+            })
+            .collect();
+        Self { terms }
+    }
+
+    /// Roll the expression, reading values through `get`.
+    pub(crate) fn roll_with(
+        &self,
+        get: impl Fn(Id<Value>) -> i32,
+        rng: &mut impl Rng,
+    ) -> RollResult {
+        let mut result = RollResult {
+            total: 0,
+            parts: Vec::new(),
+        };
+
+        for &(negative, term) in &self.terms {
+            let (dice, number) = match term {
+                Term::Dice(group) => {
+                    let dice = group.roll(rng);
+                    let sum: i64 = dice
+                        .iter()
+                        .filter(|die| die.kept)
+                        .map(|die| i64::from(die.total()))
+                        .sum();
+                    (dice, i32::try_from(sum).unwrap_or(i32::MAX))
+                }
+                Term::Constant(c) => (Vec::new(), c),
+                Term::Value(id) => (Vec::new(), get(id)),
+            };
+
+            result.push(RollPart {
+                term,
+                negative,
+                dice,
+                total: if negative {
+                    number.saturating_neg()
+                } else {
+                    number
+                },
+                source: None,
+            });
+        }
+
+        result
+    }
+}
+
+impl From<DiceGroup> for Dice {
+    fn from(dice: DiceGroup) -> Self {
+        Self::new(dice)
+    }
+}
+
+/// A single rolled die.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DieRoll {
+    /// The rolled side, followed by any rerolls of an exploding die.
+    pub faces: Vec<u16>,
+    /// Whether the die counts towards the total.
+    pub kept: bool,
+}
+
+impl DieRoll {
+    /// Sum of all faces.
+    pub fn total(&self) -> u32 {
+        self.faces.iter().copied().map(u32::from).sum()
+    }
+}
+
+/// Rolled term of a dice expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RollPart {
+    /// The rolled term.
+    pub term: Term,
+    /// Whether the term was subtracted.
+    pub negative: bool,
+    /// Every die rolled for the term, empty for constants and values.
+    pub dice: Vec<DieRoll>,
+    /// Contribution to the total, already negated for subtracted terms.
+    ///
+    /// Sums of dice too large for an `i32` are clamped.
+    pub total: i32,
+    /// Item or selection that granted the part, None for parts of the roll itself.
+    pub source: Option<ModificationSource>,
+}
+
+/// Outcome of rolling a dice expression, with every die and modifier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RollResult {
+    /// Sum of all parts, clamped to the range of an `i32`.
+    pub total: i32,
+    /// Parts in the order of the expression.
+    pub parts: Vec<RollPart>,
+}
+
+impl RollResult {
+    pub(crate) fn push(&mut self, part: RollPart) {
+        self.total = self.total.saturating_add(part.total);
+        self.parts.push(part);
+    }
+}
+
+impl Character<'_> {
+    /// Roll a dice expression, using the actual numbers of the character for its values.
+    pub fn roll_dice(&self, dice: &Dice, rng: &mut impl Rng) -> RollResult {
+        dice.roll_with(|id| self.get(id), rng)
+    }
+}
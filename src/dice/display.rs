@@ -0,0 +1,69 @@
+use super::{Dice, DiceGroup, Keep, Term};
+use crate::model::{write_id, Model};
+use std::fmt;
+
+/// Formats a dice expression, see [`Dice::display`].
+pub struct DiceDisplay<'a> {
+    dice: &'a Dice,
+    model: &'a Model,
+}
+
+impl Dice {
+    /// Format as an expression that can be read by [`Dice::parse`], using the value id strings
+    /// of `model`.
+    pub fn display<'a>(&'a self, model: &'a Model) -> DiceDisplay<'a> {
+        DiceDisplay { dice: self, model }
+    }
+}
+
+impl fmt::Display for DiceGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+        match self.keep {
+            Some(Keep::Highest(n)) => write!(f, "kh{}", n)?,
+            Some(Keep::Lowest(n)) => write!(f, "kl{}", n)?,
+            None => {}
+        }
+        if self.explode {
+            write!(f, "!")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DiceDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, (mut negative, mut term)) in self.dice.terms().enumerate() {
+            if let Term::Constant(c) = term {
+                if let Some(c) = c.checked_neg().filter(|_| c < 0) {
+                    negative = !negative;
+                    term = Term::Constant(c);
+                }
+            }
+
+            match (idx, negative) {
+                (0, true) => write!(f, "-")?,
+                (0, false) => {}
+                (_, true) => write!(f, " - ")?,
+                (_, false) => write!(f, " + ")?,
+            }
+
+            match term {
+                Term::Dice(group) => write!(f, "{}", group)?,
+                Term::Constant(c) => write!(f, "{}", c)?,
+                Term::Value(id) => {
+                    let id = self.model.values().id_str(id);
+                    // Identifiers like `d6` would be read as dice
+                    let mut chars = id.chars();
+                    if chars.next() == Some('d') && chars.next().is_some_and(|c| c.is_ascii_digit())
+                    {
+                        write!(f, "`{}`", id)?;
+                    } else {
+                        write_id(f, id)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+use super::{Dice, DiceGroup, Keep, Term};
+use crate::model::Model;
+use std::{error::Error, fmt, ops::Range};
+
+/// Reason a dice expression could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiceErrorKind {
+    /// A character that is not valid at this position.
+    UnexpectedCharacter(char),
+    /// The expression ended while more input was expected.
+    UnexpectedEnd,
+    /// A number literal that is out of range.
+    InvalidNumber,
+    /// An identifier that does not name a value of the model.
+    UnknownIdentifier(String),
+    /// Dice without sides, keeping more dice than are rolled, or exploding single-sided dice.
+    InvalidDice,
+}
+
+/// Error when parsing a dice expression, pointing to the offending bytes of the source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiceError {
+    kind: DiceErrorKind,
+    span: Range<usize>,
+}
+
+impl DiceError {
+    /// Reason of the error.
+    pub fn kind(&self) -> &DiceErrorKind {
+        &self.kind
+    }
+
+    /// Byte range of the source the error refers to.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+}
+
+impl fmt::Display for DiceErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnexpectedCharacter(c) => write!(f, "unexpected character `{}`", c),
+            Self::UnexpectedEnd => write!(f, "unexpected end of dice expression"),
+            Self::InvalidNumber => write!(f, "number out of range"),
+            Self::UnknownIdentifier(id) => write!(f, "unknown value `{}`", id),
+            Self::InvalidDice => write!(f, "invalid dice"),
+        }
+    }
+}
+
+impl fmt::Display for DiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}..{}", self.kind, self.span.start, self.span.end)
+    }
+}
+
+impl Error for DiceError {}
+
+struct Parser<'s> {
+    source: &'s str,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn rest(&self) -> &'s str {
+        &self.source[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, kind: DiceErrorKind, span: Range<usize>) -> DiceError {
+        DiceError { kind, span }
+    }
+
+    /// Error for the character at the current position.
+    fn unexpected(&self) -> DiceError {
+        match self.rest().chars().next() {
+            Some(c) => self.error(
+                DiceErrorKind::UnexpectedCharacter(c),
+                self.pos..self.pos + c.len_utf8(),
+            ),
+            None => self.error(DiceErrorKind::UnexpectedEnd, self.pos..self.pos),
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'s str {
+        let rest = self.rest();
+        let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, DiceError> {
+        let start = self.pos;
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            return Err(self.unexpected());
+        }
+        digits
+            .parse()
+            .map_err(|_| self.error(DiceErrorKind::InvalidNumber, start..self.pos))
+    }
+
+    fn starts_dice(&self) -> bool {
+        let mut chars = self.rest().chars();
+        chars.next() == Some('d') && chars.next().is_some_and(|c| c.is_ascii_digit())
+    }
+
+    fn dice(&mut self, start: usize, count: u16) -> Result<DiceGroup, DiceError> {
+        let sides: u16 = self.number()?;
+
+        let mut keep = None;
+        let mut explode = false;
+        let mut advantage = None;
+        loop {
+            if self.eat("kl") {
+                keep = Some(Keep::Lowest(self.number()?));
+            } else if self.eat("kh") || self.eat("k") {
+                keep = Some(Keep::Highest(self.number()?));
+            } else if self.eat("!") {
+                explode = true;
+            } else if self.eat("adv") {
+                advantage = Some(true);
+            } else if self.eat("dis") {
+                advantage = Some(false);
+            } else {
+                break;
+            }
+        }
+
+        let invalid = match keep {
+            Some(Keep::Highest(n)) | Some(Keep::Lowest(n)) => n > count || advantage.is_some(),
+            None => advantage.is_some() && count.checked_mul(2).is_none(),
+        };
+        if count == 0 || sides == 0 || (explode && sides == 1) || invalid {
+            return Err(self.error(DiceErrorKind::InvalidDice, start..self.pos));
+        }
+
+        let mut group = DiceGroup::new(count, sides);
+        if let Some(keep) = keep {
+            group = group.keep(keep);
+        }
+        group = match advantage {
+            Some(true) => group.advantage(),
+            Some(false) => group.disadvantage(),
+            None => group,
+        };
+        if explode {
+            group = group.explode();
+        }
+        Ok(group)
+    }
+
+    fn term(&mut self, model: &Model) -> Result<Term, DiceError> {
+        self.skip_whitespace();
+        let start = self.pos;
+
+        if self.rest().starts_with(|c: char| c.is_ascii_digit()) {
+            self.take_while(|c| c.is_ascii_digit());
+            let dice = self.starts_dice();
+            self.pos = start;
+
+            if dice {
+                let count = self.number()?;
+                self.pos += 1;
+                return self.dice(start, count).map(Term::Dice);
+            }
+            return self.number().map(Term::Constant);
+        }
+
+        if self.starts_dice() {
+            self.pos += 1;
+            return self.dice(start, 1).map(Term::Dice);
+        }
+
+        let id = if self.eat("`") {
+            let id = self.take_while(|c| c != '`');
+            if !self.eat("`") {
+                return Err(self.unexpected());
+            }
+            id
+        } else if self
+            .rest()
+            .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        {
+            self.take_while(|c| c.is_ascii_alphanumeric() || c == '_')
+        } else {
+            return Err(self.unexpected());
+        };
+
+        match model.values().find(id) {
+            Some(id) => Ok(Term::Value(id)),
+            None => Err(self.error(
+                DiceErrorKind::UnknownIdentifier(id.to_string()),
+                start..self.pos,
+            )),
+        }
+    }
+}
+
+impl Dice {
+    /// Parse a dice expression, resolving identifiers to values of `model`.
+    ///
+    /// An expression is a sum of terms separated by `+` or `-`. A term is a number, a value
+    /// identifier, or dice like `2d6`. Dice can be followed by `kh<n>` or `kl<n>` to keep the
+    /// highest or lowest dice, by `adv` or `dis` to roll twice and keep the better or worse half,
+    /// and by `!` to roll again whenever a die shows its highest side.
+    pub fn parse(source: &str, model: &Model) -> Result<Self, DiceError> {
+        let mut parser = Parser { source, pos: 0 };
+
+        parser.skip_whitespace();
+        let mut negative = parser.eat("-");
+        let mut terms = Vec::new();
+        loop {
+            terms.push((negative, parser.term(model)?));
+
+            parser.skip_whitespace();
+            if parser.eat("+") {
+                negative = false;
+            } else if parser.eat("-") {
+                negative = true;
+            } else if parser.rest().is_empty() {
+                return Ok(Self { terms });
+            } else {
+                return Err(parser.unexpected());
+            }
+        }
+    }
+}
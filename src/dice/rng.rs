@@ -0,0 +1,49 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// Source of random numbers for rolling dice.
+pub trait Rng {
+    /// Get 64 random bits.
+    fn next_u64(&mut self) -> u64;
+
+    /// Get a uniformly distributed number from 1 to `sides`.
+    fn die(&mut self, sides: u16) -> u16 {
+        let sides = u64::from(sides);
+        // Reject the incomplete range at the top to avoid bias
+        let zone = u64::MAX - u64::MAX % sides;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return (x % sides) as u16 + 1;
+            }
+        }
+    }
+}
+
+/// Small, seedable generator, see <https://prng.di.unimi.it/splitmix64.c>.
+#[derive(Clone, Debug)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// Create a generator that always produces the same numbers for the same seed.
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Create a generator with a seed that differs between calls.
+    pub fn from_entropy() -> Self {
+        Self(RandomState::new().build_hasher().finish())
+    }
+}
+
+impl Rng for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
@@ -2,6 +2,10 @@
 #![deny(missing_docs)]
 
 mod character;
+pub mod dice;
 pub mod model;
 
-pub use character::Character;
+pub use character::{
+    Character, CharacterError, DependencyStep, Duration, Event, Explanation, ItemInventory,
+    ItemState, LoadError, ModificationSource, ModificationStep, Subscription, Transaction,
+};
@@ -3,30 +3,64 @@
 mod calculation;
 mod choice;
 mod container;
+mod cycle;
+#[cfg(feature = "serde")]
+mod definition;
+mod error;
 mod front_end;
+mod group;
 mod inventory;
 mod item;
 mod modification;
+mod order;
+mod recovery;
+mod resource;
+mod roll;
+mod slot;
+mod validate;
 mod value;
 
 pub use calculation::*;
 pub use choice::*;
 pub use container::*;
+pub use cycle::CycleError;
+pub use error::ModelError;
 pub use front_end::*;
+pub use group::*;
 pub use inventory::*;
 pub use item::*;
 pub use modification::*;
+pub use recovery::*;
+pub use resource::*;
+pub use roll::*;
+pub use slot::*;
+pub use validate::*;
 pub use value::*;
 
+pub(crate) use cycle::Node;
+use order::Order;
+use std::sync::OnceLock;
+
 /// Contains a set of values and items that can be used together.
+///
+/// With the `serde` feature, a model can be loaded from and saved to a declarative definition,
+/// in which entities reference each other by id string and calculations are stored as formulas.
 #[derive(Default)]
 pub struct Model {
     choices: Container<Choice>,
     values: Container<Value>,
     inventories: Container<Inventory>,
     items: Container<Item>,
+    groups: Container<Group>,
+    rolls: Container<Roll>,
+    resources: Container<Resource>,
+    recoveries: Container<Recovery>,
+    slots: Container<Slot>,
 
     main_inventory: Option<Id<Inventory>>,
+    rounds_per_minute: Option<u32>,
+
+    order: OnceLock<Order>,
 }
 
 impl Model {
@@ -37,46 +71,368 @@ impl Model {
 
     /// Add a new choice to the Model. Id string can not alias other choice ids.
     pub fn add_choice(&mut self, id_str: impl ToString, choice: Choice) -> Id<Choice> {
-        self.choices.insert(id_str, choice)
+        self.try_add_choice(id_str, choice)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Add a new choice to the Model, unless the id string is already in use.
+    pub fn try_add_choice(
+        &mut self,
+        id_str: impl ToString,
+        choice: Choice,
+    ) -> Result<Id<Choice>, ModelError> {
+        self.choices.try_insert(id_str, choice)
     }
 
     /// Add a new value to the model. Id string can not alias other value ids.
     pub fn add_value(&mut self, id_str: impl ToString, value: Value) -> Id<Value> {
-        self.values.insert(id_str, value)
+        self.try_add_value(id_str, value)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Add a new value to the model, unless the id string is already in use.
+    pub fn try_add_value(
+        &mut self,
+        id_str: impl ToString,
+        value: Value,
+    ) -> Result<Id<Value>, ModelError> {
+        let id = self.values.try_insert(id_str, value)?;
+        self.order = OnceLock::new();
+        Ok(id)
     }
 
     /// Add a new inventory type.
     pub fn add_inventory(&mut self, id_str: impl ToString, inventory: Inventory) -> Id<Inventory> {
-        self.inventories.insert(id_str, inventory)
+        self.try_add_inventory(id_str, inventory)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Add a new inventory type, unless the id string is already in use or its limits contain
+    /// placeholders or values of another model.
+    pub fn try_add_inventory(
+        &mut self,
+        id_str: impl ToString,
+        inventory: Inventory,
+    ) -> Result<Id<Inventory>, ModelError> {
+        let limits = inventory.capacity.iter().chain(&inventory.slots);
+        self.values
+            .check(limits.clone().flat_map(Calculation::values))?;
+        if limits.clone().any(Calculation::has_placeholders) {
+            return Err(ModelError::Placeholder);
+        }
+
+        self.inventories.try_insert(id_str, inventory)
+    }
+
+    /// Add a new equipment slot. Id string can not alias other slot ids.
+    pub fn add_slot(&mut self, id_str: impl ToString, slot: Slot) -> Id<Slot> {
+        self.try_add_slot(id_str, slot)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Add a new equipment slot, unless the id string is already in use.
+    pub fn try_add_slot(
+        &mut self,
+        id_str: impl ToString,
+        slot: Slot,
+    ) -> Result<Id<Slot>, ModelError> {
+        self.slots.try_insert(id_str, slot)
     }
 
     /// Add a new item to the model. Id string can not alias other item ids.
     pub fn add_item(&mut self, id_str: impl ToString, item: Item) -> Id<Item> {
-        let id = self.items.insert(id_str, item);
+        self.try_add_item(id_str, item)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Add a new item to the model, unless the id string is already in use, its condition
+    /// contains placeholders, or it refers to ids of another model.
+    pub fn try_add_item(
+        &mut self,
+        id_str: impl ToString,
+        item: Item,
+    ) -> Result<Id<Item>, ModelError> {
+        self.values
+            .check(item.condition.iter().flat_map(Calculation::values))?;
+        self.slots.check(item.slots.iter().copied())?;
+        self.inventories.check(item.has_inventory)?;
+        if item
+            .condition
+            .as_ref()
+            .is_some_and(Calculation::has_placeholders)
+        {
+            return Err(ModelError::Placeholder);
+        }
+
+        let id = self.items.try_insert(id_str, item)?;
+        self.order = OnceLock::new();
 
         if let Some(calc) = &self.items.get(id).condition {
             for value in calc.values() {
                 self.values.get_mut(value).conditions.push(id);
             }
         }
+        for &slot in &self.items.get(id).slots {
+            let list = &mut self.slots.get_mut(slot).items;
+            if !list.contains(&id) {
+                list.push(id);
+            }
+        }
+
+        Ok(id)
+    }
+
+    /// Add a new group of values and items that can be observed together.
+    pub fn add_group(&mut self, id_str: impl ToString, group: Group) -> Id<Group> {
+        self.try_add_group(id_str, group)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Add a new group, unless the id string is already in use or it contains ids of another
+    /// model.
+    pub fn try_add_group(
+        &mut self,
+        id_str: impl ToString,
+        group: Group,
+    ) -> Result<Id<Group>, ModelError> {
+        self.values.check(group.values.iter().copied())?;
+        self.items.check(group.items.iter().copied())?;
+        self.resources.check(group.resources.iter().copied())?;
+        self.groups.try_insert(id_str, group)
+    }
+
+    /// Add a new resource.
+    pub fn add_resource(&mut self, id_str: impl ToString, resource: Resource) -> Id<Resource> {
+        self.try_add_resource(id_str, resource)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Add a new resource, unless the id string is already in use or its maximum contains
+    /// placeholders or values of another model.
+    pub fn try_add_resource(
+        &mut self,
+        id_str: impl ToString,
+        resource: Resource,
+    ) -> Result<Id<Resource>, ModelError> {
+        self.values.check(resource.maximum.values())?;
+        if resource.maximum.has_placeholders() {
+            return Err(ModelError::Placeholder);
+        }
+
+        let id = self.resources.try_insert(id_str, resource)?;
+        for value in self.resources.get(id).maximum.values() {
+            self.values.get_mut(value).resources.push(id);
+        }
+        self.order = OnceLock::new();
+        Ok(id)
+    }
 
-        id
+    /// Add a new recovery event like a long rest.
+    pub fn add_recovery(&mut self, id_str: impl ToString, recovery: Recovery) -> Id<Recovery> {
+        self.try_add_recovery(id_str, recovery)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Add a new recovery event, unless the id string is already in use or its amounts contain
+    /// placeholders, or it refers to ids of another model.
+    pub fn try_add_recovery(
+        &mut self,
+        id_str: impl ToString,
+        recovery: Recovery,
+    ) -> Result<Id<Recovery>, ModelError> {
+        self.resources
+            .check(recovery.refreshes().map(|(id, _)| id))?;
+        self.items.check(recovery.recharges().map(|(id, _)| id))?;
+        let refreshes = recovery.refreshes().map(|(_, calc)| calc);
+        let recharges = recovery.recharges().map(|(_, calc)| calc);
+        let amounts: Vec<_> = refreshes.chain(recharges).flatten().collect();
+        self.values
+            .check(amounts.iter().flat_map(|calc| calc.values()))?;
+        if amounts.into_iter().any(Calculation::has_placeholders) {
+            return Err(ModelError::Placeholder);
+        }
+
+        self.recoveries.try_insert(id_str, recovery)
+    }
+
+    /// Add a new named roll.
+    pub fn add_roll(&mut self, id_str: impl ToString, roll: Roll) -> Id<Roll> {
+        self.try_add_roll(id_str, roll)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Add a new named roll, unless the id string is already in use, its bonus contains
+    /// placeholders, or it reads values of another model.
+    pub fn try_add_roll(
+        &mut self,
+        id_str: impl ToString,
+        roll: Roll,
+    ) -> Result<Id<Roll>, ModelError> {
+        let bonus = roll.bonus.iter().flat_map(Calculation::values);
+        self.values.check(roll.dice.values().chain(bonus))?;
+        if roll
+            .bonus
+            .as_ref()
+            .is_some_and(Calculation::has_placeholders)
+        {
+            return Err(ModelError::Placeholder);
+        }
+
+        self.rolls.try_insert(id_str, roll)
+    }
+
+    /// When item `from` is equipped, roll `to` will be changed accordingly.
+    pub fn add_roll_modifier(&mut self, from: Id<Item>, to: Id<Roll>, modifier: RollModifier) {
+        if let Err(err) = self.try_add_roll_modifier(from, to, modifier) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Add a roll modifier to an item, unless its bonus contains placeholders or any id does not
+    /// belong to this model.
+    pub fn try_add_roll_modifier(
+        &mut self,
+        from: Id<Item>,
+        to: Id<Roll>,
+        modifier: RollModifier,
+    ) -> Result<(), ModelError> {
+        self.items.check(Some(from))?;
+        self.rolls.check(Some(to))?;
+        self.values.check(
+            modifier
+                .calculation()
+                .into_iter()
+                .flat_map(Calculation::values),
+        )?;
+        if modifier
+            .calculation()
+            .is_some_and(Calculation::has_placeholders)
+        {
+            return Err(ModelError::Placeholder);
+        }
+
+        self.items.get_mut(from).roll_modifiers.push((to, modifier));
+        let list = &mut self.rolls.get_mut(to).modifying_items;
+        if !list.contains(&from) {
+            list.push(from);
+        }
+        Ok(())
     }
 
     /// Value of `from` will be added to `to` with the given factor.
+    ///
+    /// Panics if the dependency would create a cycle.
     pub fn add_dependency(&mut self, id: Id<Value>, calc: impl IntoCalculation) {
+        if let Err(err) = self.try_add_dependency(id, calc) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Add a dependency, unless it contains placeholders or values of another model, or would
+    /// create a cycle.
+    pub fn try_add_dependency(
+        &mut self,
+        id: Id<Value>,
+        calc: impl IntoCalculation,
+    ) -> Result<(), ModelError> {
         let calc = calc.into_calc();
+        self.values.check(calc.values().chain(Some(id)))?;
+        if calc.has_placeholders() {
+            return Err(ModelError::Placeholder);
+        }
 
-        // TODO: prevent cycles
+        self.check_cycles(calc.values().map(Node::Value), Node::Value(id))?;
         for dependency in calc.values() {
             self.values.get_mut(dependency).dependents.push(id);
         }
 
         self.values.get_mut(id).dependencies.push(calc);
+        self.order = OnceLock::new();
+        Ok(())
+    }
+
+    /// The load of every carried inventory of type `inventory` will be added to value `id`, e.g.
+    /// to compute the burden of a character from the content of their main inventory.
+    ///
+    /// Panics if either id does not belong to this model.
+    pub fn add_load(&mut self, id: Id<Value>, inventory: Id<Inventory>, load: Load) {
+        if let Err(err) = self.try_add_load(id, inventory, load) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Add the load of an inventory type to a value, unless either id does not belong to this
+    /// model.
+    pub fn try_add_load(
+        &mut self,
+        id: Id<Value>,
+        inventory: Id<Inventory>,
+        load: Load,
+    ) -> Result<(), ModelError> {
+        self.values.check(Some(id))?;
+        self.inventories.check(Some(inventory))?;
+
+        self.values.get_mut(id).loads.push((inventory, load));
+        self.inventories.get_mut(inventory).loaded.push(id);
+        Ok(())
     }
 
     /// Add a selection to a choice.
+    ///
+    /// Panics if the modifications of the selection would create a cycle.
     pub fn add_selection(&mut self, id: Id<Choice>, selection: Selection) {
+        if let Err(err) = self.try_add_selection(id, selection) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Add a selection to a choice, unless its modifications would create a cycle or it refers
+    /// to ids of another model.
+    pub fn try_add_selection(
+        &mut self,
+        id: Id<Choice>,
+        selection: Selection,
+    ) -> Result<(), ModelError> {
+        self.choices.check(Some(id))?;
+        self.rolls
+            .check(selection.roll_modifiers.iter().map(|&(roll, _)| roll))?;
+        let modifications = selection
+            .modifications
+            .iter()
+            .flat_map(|(&value, modification)| {
+                modification.calculation().values().chain(Some(value))
+            });
+        let bonuses = selection
+            .roll_modifiers
+            .iter()
+            .filter_map(|(_, modifier)| modifier.calculation())
+            .flat_map(Calculation::values);
+        self.values.check(modifications.chain(bonuses))?;
+        if selection.roll_modifiers.iter().any(|(_, modifier)| {
+            modifier
+                .calculation()
+                .is_some_and(Calculation::has_placeholders)
+        }) {
+            return Err(ModelError::Placeholder);
+        }
+
+        // Modifications of the same selection can form a cycle among themselves, so their
+        // edges are added one by one and removed again on failure.
+        let mut added = Vec::new();
+        for (&value, modification) in &selection.modifications {
+            let sources = modification.inputs(value).map(Node::Value);
+            if let Err(err) = self.check_cycles(sources, Node::Value(value)) {
+                for (input, value) in added {
+                    self.values
+                        .get_mut(input)
+                        .dependents
+                        .retain(|&e| e != value);
+                }
+                return Err(err.into());
+            }
+            added.extend(self.add_dependents(value, modification));
+        }
+
         for &value in selection.modifications.keys() {
             let list = &mut self.values.get_mut(value).modifying_choices;
             if list.iter().all(|&e| e != id) {
@@ -84,7 +440,33 @@ impl Model {
             }
         }
 
+        for (roll, _) in &selection.roll_modifiers {
+            let list = &mut self.rolls.get_mut(*roll).modifying_choices;
+            if !list.contains(&id) {
+                list.push(id);
+            }
+        }
+
         self.choices.get_mut(id).options.push(selection);
+        self.order = OnceLock::new();
+        Ok(())
+    }
+
+    /// Register the value as dependent on the inputs of a modification. Returns the new edges.
+    fn add_dependents(
+        &mut self,
+        id: Id<Value>,
+        modification: &Modification,
+    ) -> Vec<(Id<Value>, Id<Value>)> {
+        let mut added = Vec::new();
+        for input in modification.inputs(id) {
+            let list = &mut self.values.get_mut(input).dependents;
+            if list.iter().all(|&e| e != id) {
+                list.push(id);
+                added.push((input, id));
+            }
+        }
+        added
     }
 
     /// Get the type for a characters main inventory.
@@ -97,20 +479,54 @@ impl Model {
         self.main_inventory = Some(id)
     }
 
+    /// Get the number of rounds in a minute, used for effects that last minutes. Defaults to
+    /// 10, assuming six second rounds.
+    pub fn rounds_per_minute(&self) -> u32 {
+        self.rounds_per_minute.unwrap_or(10)
+    }
+
+    /// Set the number of rounds in a minute.
+    pub fn set_rounds_per_minute(&mut self, rounds: u32) {
+        self.rounds_per_minute = Some(rounds)
+    }
+
     /// When item `from` is equipped, `to` will be modified accordingly.
-    pub fn add_modification(
+    ///
+    /// Panics if the modification would create a cycle.
+    pub fn add_modification(&mut self, from: Id<Item>, to: Id<Value>, modification: Modification) {
+        if let Err(err) = self.try_add_modification(from, to, modification) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Add a modification to an item, unless it would create a cycle or any id does not belong
+    /// to this model.
+    pub fn try_add_modification(
         &mut self,
         from: Id<Item>,
         to: Id<Value>,
         mut modification: Modification,
-    ) {
-        // TODO: prevent cycles
+    ) -> Result<(), ModelError> {
+        self.items.check(Some(from))?;
+        self.values
+            .check(modification.calculation().values().chain(Some(to)))?;
         modification.set_value(to);
+
+        let item = self.items.get(from);
+        let sources = modification
+            .inputs(to)
+            .map(Node::Value)
+            .chain(item.condition.as_ref().map(|_| Node::Item(from)));
+        self.check_cycles(sources, Node::Value(to))?;
+
+        self.add_dependents(to, &modification);
         self.items
             .get_mut(from)
             .modifications
             .insert(to, modification);
         self.values.get_mut(to).modifying_items.push(from);
+        self.order = OnceLock::new();
+        Ok(())
     }
 
     /// Returns a reference to the Container of Choices.
@@ -132,4 +548,29 @@ impl Model {
     pub fn items(&self) -> &Container<Item> {
         &self.items
     }
+
+    /// Returns a reference to the Container of Groups.
+    pub fn groups(&self) -> &Container<Group> {
+        &self.groups
+    }
+
+    /// Returns a reference to the Container of Resources.
+    pub fn resources(&self) -> &Container<Resource> {
+        &self.resources
+    }
+
+    /// Returns a reference to the Container of Recovery events.
+    pub fn recoveries(&self) -> &Container<Recovery> {
+        &self.recoveries
+    }
+
+    /// Returns a reference to the Container of equipment Slots.
+    pub fn slots(&self) -> &Container<Slot> {
+        &self.slots
+    }
+
+    /// Returns a reference to the Container of Rolls.
+    pub fn rolls(&self) -> &Container<Roll> {
+        &self.rolls
+    }
 }
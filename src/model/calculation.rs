@@ -1,3 +1,9 @@
+mod display;
+mod parse;
+
+pub use self::display::*;
+pub use self::parse::*;
+
 use super::{Id, Value};
 use std::{
     cmp::{max, min},
@@ -67,6 +73,18 @@ impl BinaryOp {
             Self::Or => (a != 0 || b != 0) as i32,
         }
     }
+
+    /// Like `exec`, but returns None instead of overflowing or dividing by zero.
+    fn checked_exec(&self, a: i32, b: i32) -> Option<i32> {
+        match self {
+            Self::Add => a.checked_add(b),
+            Self::Sub => a.checked_sub(b),
+            Self::Mul => a.checked_mul(b),
+            Self::Div(_) if b == 0 => None,
+            Self::Rem => a.checked_rem(b),
+            _ => Some(self.exec(a, b)),
+        }
+    }
 }
 
 #[derive(PartialEq)]
@@ -84,6 +102,15 @@ impl UnaryOp {
             Self::Not => (val == 0) as i32,
         }
     }
+
+    /// Like `exec`, but returns None instead of overflowing.
+    fn checked_exec(&self, val: i32) -> Option<i32> {
+        match self {
+            Self::Abs => val.checked_abs(),
+            Self::Neg => val.checked_neg(),
+            Self::Not => Some(self.exec(val)),
+        }
+    }
 }
 
 #[derive(PartialEq)]
@@ -273,6 +300,56 @@ impl Calculation {
         self.binary(other.into_calc(), BinaryOp::Or)
     }
 
+    /// Returns true if the calculation contains placeholders and can not be evaluated yet.
+    pub fn has_placeholders(&self) -> bool {
+        self.storage.contains(&Element::Placeholder)
+    }
+
+    /// Number of operations nested into each other, which bounds the recursion when evaluating.
+    pub(crate) fn depth(&self) -> usize {
+        // Operands are always stored before the operations using them
+        let mut depths = Vec::with_capacity(self.storage.len());
+        for element in &self.storage {
+            let depth = match element {
+                Element::Const(_) | Element::Value(_) | Element::Placeholder => 0,
+                Element::MultiplyF(_, _, val) | Element::Unary(_, val) => depths[*val] + 1,
+                Element::Binary(_, a, b) => usize::max(depths[*a], depths[*b]) + 1,
+            };
+            depths.push(depth);
+        }
+        depths[self.output]
+    }
+
+    /// Result of the calculation if it does not depend on any values.
+    pub(crate) fn constant(&self) -> Option<i32> {
+        self.const_eval(self.output)
+    }
+
+    fn const_eval(&self, idx: usize) -> Option<i32> {
+        let eval = |&idx| self.const_eval(idx);
+
+        match &self.storage[idx] {
+            Element::Const(v) => Some(*v),
+            Element::Value(_) | Element::Placeholder => None,
+
+            Element::MultiplyF(r, fac, val) => Some(r.apply(eval(val)? as f64 * (*fac as f64))),
+            Element::Unary(op, val) => op.checked_exec(eval(val)?),
+            Element::Binary(op, a, b) => op.checked_exec(eval(a)?, eval(b)?),
+        }
+    }
+
+    /// Divisors of all divisions and remainders, with their value if they are constant. The flag
+    /// is set for remainders.
+    pub(crate) fn divisors(&self) -> impl Iterator<Item = (bool, Option<i32>)> + '_ {
+        self.storage
+            .iter()
+            .filter_map(move |element| match element {
+                Element::Binary(BinaryOp::Div(_), _, b) => Some((false, self.const_eval(*b))),
+                Element::Binary(BinaryOp::Rem, _, b) => Some((true, self.const_eval(*b))),
+                _ => None,
+            })
+    }
+
     pub(crate) fn values(&self) -> impl Iterator<Item = Id<Value>> + '_ {
         self.values.iter().cloned()
     }
@@ -284,7 +361,7 @@ impl Calculation {
     fn eval(&self, values: &[i32], idx: usize) -> i32 {
         let eval = |&idx| self.eval(values, idx);
 
-        match &self.storage[idx as usize] {
+        match &self.storage[idx] {
             Element::Const(v) => *v,
             Element::Value(idx) => values[*idx],
 
@@ -0,0 +1,208 @@
+use super::{BinaryOp, Calculation, Element, Rounding, UnaryOp};
+use crate::model::{Id, Model, Value};
+use std::fmt;
+
+/// Formats a calculation as a formula, see [`Calculation::display`].
+pub struct CalculationDisplay<'a> {
+    calc: &'a Calculation,
+    model: &'a Model,
+    placeholder: Option<Id<Value>>,
+}
+
+const PRIMARY: u8 = 7;
+const PREFIX: u8 = 6;
+const PRODUCT: u8 = 5;
+
+impl BinaryOp {
+    /// Symbol and precedence of infix operators, None for functions.
+    fn infix(&self) -> Option<(&'static str, u8)> {
+        let infix = match self {
+            Self::Or => ("||", 0),
+            Self::And => ("&&", 1),
+            Self::Eq => ("==", 2),
+            Self::Ne => ("!=", 2),
+            Self::Lt => ("<", 3),
+            Self::Le => ("<=", 3),
+            Self::Gt => (">", 3),
+            Self::Ge => (">=", 3),
+            Self::Add => ("+", 4),
+            Self::Sub => ("-", 4),
+            Self::Mul => ("*", PRODUCT),
+            Self::Div(Rounding::Floor) => ("/", PRODUCT),
+            Self::Rem => ("%", PRODUCT),
+            Self::Div(_) | Self::Min | Self::Max => return None,
+        };
+        Some(infix)
+    }
+}
+
+impl Rounding {
+    fn function(&self) -> &'static str {
+        match self {
+            Self::Floor => "floor",
+            Self::Nearest => "round",
+            Self::Ceil => "ceil",
+        }
+    }
+}
+
+pub(crate) fn write_id(f: &mut fmt::Formatter, id: &str) -> fmt::Result {
+    let mut chars = id.chars();
+    let plain = id != "_"
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if plain {
+        write!(f, "{}", id)
+    } else {
+        write!(f, "`{}`", id)
+    }
+}
+
+fn write_float(f: &mut fmt::Formatter, x: f32) -> fmt::Result {
+    let text = x.to_string();
+    if text.contains('.') {
+        write!(f, "{}", text)
+    } else {
+        write!(f, "{}.0", text)
+    }
+}
+
+impl Calculation {
+    /// Format as a formula that can be read by [`Calculation::parse`], using the value id strings
+    /// of `model`.
+    pub fn display<'a>(&'a self, model: &'a Model) -> CalculationDisplay<'a> {
+        CalculationDisplay {
+            calc: self,
+            model,
+            placeholder: None,
+        }
+    }
+
+    /// Format with `id` written as a placeholder, used for modifications of that value.
+    #[cfg(feature = "serde")]
+    pub(crate) fn display_modification<'a>(
+        &'a self,
+        model: &'a Model,
+        id: Id<Value>,
+    ) -> CalculationDisplay<'a> {
+        CalculationDisplay {
+            calc: self,
+            model,
+            placeholder: Some(id),
+        }
+    }
+
+    fn precedence(&self, idx: usize) -> u8 {
+        match &self.storage[idx] {
+            Element::Const(c) if *c < 0 => PREFIX,
+            Element::MultiplyF(Rounding::Floor, _, _) => PRODUCT,
+            Element::Unary(UnaryOp::Neg, _) | Element::Unary(UnaryOp::Not, _) => PREFIX,
+            Element::Binary(op, _, _) => op.infix().map_or(PRIMARY, |(_, p)| p),
+            _ => PRIMARY,
+        }
+    }
+
+    /// Write the element at `idx`, in parentheses if it binds weaker than `precedence`.
+    fn write_operand(
+        &self,
+        f: &mut fmt::Formatter,
+        idx: usize,
+        precedence: u8,
+        name: &dyn Fn(&mut fmt::Formatter, Id<Value>) -> fmt::Result,
+    ) -> fmt::Result {
+        if self.precedence(idx) < precedence {
+            write!(f, "(")?;
+            self.write_element(f, idx, name)?;
+            write!(f, ")")
+        } else {
+            self.write_element(f, idx, name)
+        }
+    }
+
+    fn write_element(
+        &self,
+        f: &mut fmt::Formatter,
+        idx: usize,
+        name: &dyn Fn(&mut fmt::Formatter, Id<Value>) -> fmt::Result,
+    ) -> fmt::Result {
+        match &self.storage[idx] {
+            Element::Const(c) => write!(f, "{}", c),
+            Element::Value(val) => name(f, self.values[*val]),
+            Element::Placeholder => write!(f, "_"),
+
+            Element::MultiplyF(r, fac, val) => {
+                if *r != Rounding::Floor {
+                    write!(f, "{}(", r.function())?;
+                }
+                self.write_operand(f, *val, PRODUCT, name)?;
+                write!(f, " * ")?;
+                write_float(f, *fac)?;
+                if *r != Rounding::Floor {
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+
+            Element::Unary(UnaryOp::Abs, val) => {
+                write!(f, "abs(")?;
+                self.write_element(f, *val, name)?;
+                write!(f, ")")
+            }
+            Element::Unary(op, val) => {
+                write!(f, "{}", if *op == UnaryOp::Neg { "-" } else { "!" })?;
+                match &self.storage[*val] {
+                    // Avoid folding into a negative constant when parsed again
+                    Element::Const(c) if *op == UnaryOp::Neg && *c >= 0 => write!(f, "({})", c),
+                    _ => self.write_operand(f, *val, PREFIX, name),
+                }
+            }
+
+            Element::Binary(op, a, b) => match op.infix() {
+                Some((symbol, precedence)) => {
+                    self.write_operand(f, *a, precedence, name)?;
+                    write!(f, " {} ", symbol)?;
+                    self.write_operand(f, *b, precedence + 1, name)
+                }
+                None => {
+                    let (function, separator) = match op {
+                        BinaryOp::Div(r) => (r.function(), " / "),
+                        BinaryOp::Min => ("min", ", "),
+                        _ => ("max", ", "),
+                    };
+                    let (lhs, rhs) = match op {
+                        BinaryOp::Div(_) => (PRODUCT, PRODUCT + 1),
+                        _ => (0, 0),
+                    };
+
+                    write!(f, "{}(", function)?;
+                    self.write_operand(f, *a, lhs, name)?;
+                    write!(f, "{}", separator)?;
+                    self.write_operand(f, *b, rhs, name)?;
+                    write!(f, ")")
+                }
+            },
+        }
+    }
+}
+
+impl fmt::Display for CalculationDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let values = self.model.values();
+        self.calc.write_element(f, self.calc.output, &|f, id| {
+            if Some(id) == self.placeholder {
+                write!(f, "_")
+            } else {
+                write_id(f, values.id_str(id))
+            }
+        })
+    }
+}
+
+impl fmt::Debug for Calculation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_element(f, self.output, &|f, id| write!(f, "#{}", id.0))
+    }
+}
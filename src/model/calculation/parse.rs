@@ -0,0 +1,508 @@
+use super::{BinaryOp, Calculation, Element, Rounding, UnaryOp};
+use crate::model::{Container, Model, Value};
+use std::{convert::TryFrom, error::Error, fmt, ops::Range};
+
+/// Reason a formula could not be parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseErrorKind {
+    /// A character that is not part of the formula language.
+    UnexpectedCharacter(char),
+    /// A token that is not valid at this position.
+    UnexpectedToken(String),
+    /// The formula ended while more input was expected.
+    UnexpectedEnd,
+    /// A number literal that is out of range.
+    InvalidNumber,
+    /// An identifier that does not name a value of the model.
+    UnknownIdentifier(String),
+    /// A function that does not exist.
+    UnknownFunction(String),
+    /// A function was called with the wrong number of arguments.
+    ArgumentCount {
+        /// Name of the function.
+        function: String,
+        /// Number of arguments the function takes.
+        expected: usize,
+        /// Number of arguments that were supplied.
+        found: usize,
+    },
+    /// A rounding function was applied to something other than a division or a float
+    /// multiplication.
+    NothingToRound,
+    /// A float literal was used somewhere other than as a factor.
+    MisplacedFloat,
+    /// Parentheses, function calls or operators are nested too deeply.
+    TooDeep,
+}
+
+/// Error when parsing a formula, pointing to the offending bytes of the source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    span: Range<usize>,
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: ParseErrorKind, span: Range<usize>) -> Self {
+        Self { kind, span }
+    }
+
+    /// Reason of the error.
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// Byte range of the source the error refers to.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnexpectedCharacter(c) => write!(f, "unexpected character `{}`", c),
+            Self::UnexpectedToken(token) => write!(f, "unexpected `{}`", token),
+            Self::UnexpectedEnd => write!(f, "unexpected end of formula"),
+            Self::InvalidNumber => write!(f, "number out of range"),
+            Self::UnknownIdentifier(id) => write!(f, "unknown value `{}`", id),
+            Self::UnknownFunction(name) => write!(f, "unknown function `{}`", name),
+            Self::ArgumentCount {
+                function,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` takes {} argument(s) but {} were supplied",
+                function, expected, found
+            ),
+            Self::NothingToRound => write!(
+                f,
+                "rounding requires a division or multiplication with a float"
+            ),
+            Self::MisplacedFloat => write!(f, "floats can only be used as factors"),
+            Self::TooDeep => write!(f, "formula is nested too deeply"),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}..{}", self.kind, self.span.start, self.span.end)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Token<'s> {
+    Int(u32),
+    Float(f32),
+    Ident(&'s str),
+    Placeholder,
+    Symbol(&'static str),
+    End,
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Int(i) => write!(f, "{}", i),
+            Self::Float(x) => write!(f, "{:?}", x),
+            Self::Ident(id) => write!(f, "{}", id),
+            Self::Placeholder => write!(f, "_"),
+            Self::Symbol(s) => write!(f, "{}", s),
+            Self::End => write!(f, "end of formula"),
+        }
+    }
+}
+
+/// Symbols ordered so that longer symbols are matched first.
+const SYMBOLS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!", "(", ")", ",",
+];
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token<'_>, Range<usize>)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut rest = source.char_indices().peekable();
+
+    while let Some(&(start, c)) = rest.peek() {
+        if c.is_whitespace() {
+            rest.next();
+        } else if c.is_ascii_digit() {
+            let mut end = start;
+            let mut float = false;
+            while let Some(&(idx, c)) = rest.peek() {
+                if c.is_ascii_digit() || (c == '.' && !float) {
+                    float |= c == '.';
+                    end = idx + 1;
+                    rest.next();
+                } else {
+                    break;
+                }
+            }
+
+            let text = &source[start..end];
+            let token = if float {
+                text.parse().map(Token::Float).ok()
+            } else {
+                text.parse().map(Token::Int).ok()
+            };
+            let token =
+                token.ok_or_else(|| ParseError::new(ParseErrorKind::InvalidNumber, start..end))?;
+            tokens.push((token, start..end));
+        } else if is_ident_start(c) {
+            let mut end = start;
+            while let Some(&(idx, c)) = rest.peek() {
+                if is_ident_continue(c) {
+                    end = idx + c.len_utf8();
+                    rest.next();
+                } else {
+                    break;
+                }
+            }
+
+            let token = match &source[start..end] {
+                "_" => Token::Placeholder,
+                id => Token::Ident(id),
+            };
+            tokens.push((token, start..end));
+        } else if c == '`' {
+            rest.next();
+            let end = loop {
+                match rest.next() {
+                    Some((idx, '`')) => break idx,
+                    Some(_) => {}
+                    None => {
+                        return Err(ParseError::new(
+                            ParseErrorKind::UnexpectedEnd,
+                            source.len()..source.len(),
+                        ))
+                    }
+                }
+            };
+            tokens.push((Token::Ident(&source[start + 1..end]), start..end + 1));
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| source[start..].starts_with(*s)) {
+            for _ in 0..symbol.len() {
+                rest.next();
+            }
+            tokens.push((Token::Symbol(symbol), start..start + symbol.len()));
+        } else {
+            return Err(ParseError::new(
+                ParseErrorKind::UnexpectedCharacter(c),
+                start..start + c.len_utf8(),
+            ));
+        }
+    }
+
+    tokens.push((Token::End, source.len()..source.len()));
+    Ok(tokens)
+}
+
+/// Intermediate result, floats are only allowed as direct factors.
+enum Operand {
+    Calc(Calculation),
+    Float(f32, Range<usize>),
+}
+
+impl Operand {
+    fn calc(self) -> Result<Calculation, ParseError> {
+        match self {
+            Self::Calc(calc) => Ok(calc),
+            Self::Float(_, span) => Err(ParseError::new(ParseErrorKind::MisplacedFloat, span)),
+        }
+    }
+}
+
+type Level = &'static [(&'static str, fn() -> BinaryOp)];
+
+/// Binary operators, from lowest to highest precedence.
+const LEVELS: &[Level] = &[
+    &[("||", || BinaryOp::Or)],
+    &[("&&", || BinaryOp::And)],
+    &[("==", || BinaryOp::Eq), ("!=", || BinaryOp::Ne)],
+    &[
+        ("<", || BinaryOp::Lt),
+        ("<=", || BinaryOp::Le),
+        (">", || BinaryOp::Gt),
+        (">=", || BinaryOp::Ge),
+    ],
+    &[("+", || BinaryOp::Add), ("-", || BinaryOp::Sub)],
+    &[
+        ("*", || BinaryOp::Mul),
+        ("/", || BinaryOp::Div(Rounding::Floor)),
+        ("%", || BinaryOp::Rem),
+    ],
+];
+
+/// Nesting depth of parentheses, function calls and unary operators, limiting the recursion of
+/// the parser.
+const MAX_DEPTH: usize = 32;
+
+/// Depth of the parsed calculation, limiting the recursion when evaluating long chains of
+/// operators like `1 + 1 + ...`.
+const MAX_CALCULATION_DEPTH: usize = 256;
+
+struct Parser<'s, 'v> {
+    tokens: Vec<(Token<'s>, Range<usize>)>,
+    pos: usize,
+    depth: usize,
+    values: &'v Container<Value>,
+}
+
+impl<'s> Parser<'s, '_> {
+    fn peek(&self) -> Token<'s> {
+        self.tokens[self.pos].0
+    }
+
+    fn span(&self) -> Range<usize> {
+        self.tokens[self.pos].1.clone()
+    }
+
+    fn advance(&mut self) -> (Token<'s>, Range<usize>) {
+        let token = self.tokens[self.pos].clone();
+        if token.0 != Token::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn unexpected(&self) -> ParseError {
+        let kind = match self.peek() {
+            Token::End => ParseErrorKind::UnexpectedEnd,
+            token => ParseErrorKind::UnexpectedToken(token.to_string()),
+        };
+        ParseError::new(kind, self.span())
+    }
+
+    /// Run `f` one nesting level deeper, failing with an error pointing to `span` past the limit.
+    fn nested<T>(
+        &mut self,
+        span: Range<usize>,
+        f: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(ParseError::new(ParseErrorKind::TooDeep, span));
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<(), ParseError> {
+        if self.peek() == Token::Symbol(symbol) {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Operand, ParseError> {
+        let ops = match LEVELS.get(level) {
+            Some(ops) => ops,
+            None => return self.unary(),
+        };
+
+        let mut lhs = self.binary(level + 1)?;
+        loop {
+            let op = match self.peek() {
+                Token::Symbol(s) => ops.iter().find(|(symbol, _)| *symbol == s),
+                _ => None,
+            };
+            let op = match op {
+                Some((_, op)) => op(),
+                None => return Ok(lhs),
+            };
+            self.advance();
+            let rhs = self.binary(level + 1)?;
+
+            lhs = match (op, lhs, rhs) {
+                (BinaryOp::Mul, Operand::Calc(calc), Operand::Float(f, _))
+                | (BinaryOp::Mul, Operand::Float(f, _), Operand::Calc(calc)) => {
+                    Operand::Calc(calc.mul_f(Rounding::Floor, f))
+                }
+                (op, lhs, rhs) => Operand::Calc(lhs.calc()?.binary(rhs.calc()?, op)),
+            };
+        }
+    }
+
+    fn unary(&mut self) -> Result<Operand, ParseError> {
+        let op = match self.peek() {
+            Token::Symbol("-") => UnaryOp::Neg,
+            Token::Symbol("!") => UnaryOp::Not,
+            _ => return self.primary(),
+        };
+        let span = self.advance().1;
+
+        // Negative literals are folded into constants
+        if op == UnaryOp::Neg {
+            match self.peek() {
+                Token::Int(i) => {
+                    let span = span.start..self.advance().1.end;
+                    let c = i32::try_from(-i64::from(i))
+                        .map_err(|_| ParseError::new(ParseErrorKind::InvalidNumber, span))?;
+                    return Ok(Operand::Calc(c.into()));
+                }
+                Token::Float(f) => {
+                    let span = span.start..self.advance().1.end;
+                    return Ok(Operand::Float(-f, span));
+                }
+                _ => {}
+            }
+        }
+
+        let operand = self.nested(span, Self::unary)?;
+        Ok(Operand::Calc(operand.calc()?.unary(op)))
+    }
+
+    fn primary(&mut self) -> Result<Operand, ParseError> {
+        match self.peek() {
+            Token::Int(_) | Token::Float(_) | Token::Placeholder | Token::Ident(_) => {}
+            Token::Symbol("(") => {}
+            _ => return Err(self.unexpected()),
+        }
+
+        let (token, span) = self.advance();
+        match token {
+            Token::Int(i) => i32::try_from(i)
+                .map(|c| Operand::Calc(c.into()))
+                .map_err(|_| ParseError::new(ParseErrorKind::InvalidNumber, span)),
+            Token::Float(f) => Ok(Operand::Float(f, span)),
+            Token::Placeholder => Ok(Operand::Calc(Calculation::placeholder())),
+            Token::Ident(name) if self.peek() == Token::Symbol("(") => {
+                self.nested(span.clone(), |parser| parser.call(name, span))
+            }
+            Token::Ident(id) => match self.values.find(id) {
+                Some(id) => Ok(Operand::Calc(id.into())),
+                None => Err(ParseError::new(
+                    ParseErrorKind::UnknownIdentifier(id.to_string()),
+                    span,
+                )),
+            },
+            Token::Symbol("(") => self.nested(span, |parser| {
+                let inner = parser.binary(0)?;
+                parser.expect(")")?;
+                Ok(inner)
+            }),
+            _ => unreachable!(),
+        }
+    }
+
+    fn call(&mut self, name: &str, span: Range<usize>) -> Result<Operand, ParseError> {
+        self.expect("(")?;
+
+        let mut args = Vec::new();
+        if self.peek() != Token::Symbol(")") {
+            loop {
+                let start = self.span().start;
+                let arg = self.binary(0)?.calc()?;
+                args.push((arg, start..self.tokens[self.pos - 1].1.end));
+
+                if self.peek() == Token::Symbol(",") {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+        }
+        let span = span.start..self.span().end;
+        self.expect(")")?;
+
+        let expected = match name {
+            "abs" | "floor" | "ceil" | "round" => 1,
+            "min" | "max" => 2,
+            _ => {
+                return Err(ParseError::new(
+                    ParseErrorKind::UnknownFunction(name.to_string()),
+                    span,
+                ))
+            }
+        };
+        if args.len() != expected {
+            let kind = ParseErrorKind::ArgumentCount {
+                function: name.to_string(),
+                expected,
+                found: args.len(),
+            };
+            return Err(ParseError::new(kind, span));
+        }
+
+        let mut args = args.into_iter();
+        let (a, a_span) = args.next().unwrap();
+        let result = match name {
+            "abs" => a.abs(),
+            "min" => a.min(args.next().unwrap().0),
+            "max" => a.max(args.next().unwrap().0),
+            rounding => {
+                let rounding = match rounding {
+                    "floor" => Rounding::Floor,
+                    "ceil" => Rounding::Ceil,
+                    _ => Rounding::Nearest,
+                };
+
+                let mut a = a;
+                if !a.set_rounding(rounding) {
+                    return Err(ParseError::new(ParseErrorKind::NothingToRound, a_span));
+                }
+                a
+            }
+        };
+
+        Ok(Operand::Calc(result))
+    }
+}
+
+impl Calculation {
+    /// Parse a calculation from its text representation.
+    ///
+    /// Identifiers refer to value id strings of `model`, ids that are not plain identifiers can
+    /// be quoted with backticks. `_` is a placeholder. The available operators are, from lowest
+    /// to highest precedence: `||`, `&&`, `==` `!=`, `<` `<=` `>` `>=`, `+` `-`, `*` `/` `%` and
+    /// the prefix operators `-` `!`. Functions are `abs(a)`, `min(a, b)`, `max(a, b)` and the
+    /// rounding functions `floor`, `ceil` and `round`, which apply to a division or a
+    /// multiplication with a float literal (e.g. `ceil(level * 1.5)`). Divisions round down by
+    /// default.
+    pub fn parse(source: &str, model: &Model) -> Result<Self, ParseError> {
+        Self::parse_with(source, model.values())
+    }
+
+    pub(crate) fn parse_with(source: &str, values: &Container<Value>) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+            depth: 0,
+            values,
+        };
+
+        let result = parser.binary(0)?.calc()?;
+        if parser.peek() != Token::End {
+            return Err(parser.unexpected());
+        }
+        if result.depth() > MAX_CALCULATION_DEPTH {
+            return Err(ParseError::new(ParseErrorKind::TooDeep, 0..source.len()));
+        }
+
+        Ok(result)
+    }
+
+    /// Change the rounding of the outermost operation. Returns false if it does not round.
+    fn set_rounding(&mut self, rounding: Rounding) -> bool {
+        match &mut self.storage[self.output] {
+            Element::Binary(BinaryOp::Div(r), _, _) | Element::MultiplyF(r, _, _) => {
+                *r = rounding;
+                true
+            }
+            _ => false,
+        }
+    }
+}
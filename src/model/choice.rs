@@ -1,10 +1,11 @@
-use super::{FrontEnd, Id, Modification, Value};
+use super::{FrontEnd, Id, Modification, Roll, RollModifier, Value};
 use std::collections::HashMap;
 
 /// Part of a Choice.
 pub struct Selection {
     pub(crate) front_end: Option<FrontEnd>,
     pub(crate) modifications: HashMap<Id<Value>, Modification>,
+    pub(crate) roll_modifiers: Vec<(Id<Roll>, RollModifier)>,
 }
 
 /// Represents a set of Selections. The Character will have to have exactly one Selection active at a time.
@@ -25,6 +26,7 @@ impl Selection {
                     (id, modification)
                 })
                 .collect(),
+            roll_modifiers: Vec::new(),
         }
     }
 
@@ -33,6 +35,12 @@ impl Selection {
         self.front_end = Some(front_end);
         self
     }
+
+    /// Change a roll while this selection is active.
+    pub fn roll_modifier(mut self, roll: Id<Roll>, modifier: RollModifier) -> Self {
+        self.roll_modifiers.push((roll, modifier));
+        self
+    }
 }
 
 impl Choice {
@@ -1,3 +1,4 @@
+use super::ModelError;
 use derivative::Derivative;
 use std::collections::HashMap;
 use std::marker::PhantomData;
@@ -8,10 +9,11 @@ use std::ops::{Index, IndexMut};
 #[derivative(Default(bound = ""))]
 pub struct Container<T> {
     values: Vec<T>,
+    id_strs: Vec<String>,
     ids: HashMap<String, Id<T>>,
 }
 
-///
+/// Type-checked index into a Container.
 #[derive(Derivative)]
 #[derivative(
     Clone(bound = ""),
@@ -30,20 +32,43 @@ impl<T> Container<T> {
     }
 
     /// Add a new value with id `id_str`. The id can not alias other ids.
-    pub(crate) fn insert(&mut self, id_str: impl ToString, value: T) -> Id<T> {
+    pub(crate) fn try_insert(
+        &mut self,
+        id_str: impl ToString,
+        value: T,
+    ) -> Result<Id<T>, ModelError> {
         let id = Id::new(self.values.len());
 
         let id_str = id_str.to_string();
-        assert!(self.ids.get(&id_str).is_none());
-        self.ids.insert(id_str, id);
+        if self.ids.contains_key(&id_str) {
+            return Err(ModelError::DuplicateId(id_str));
+        }
+        self.ids.insert(id_str.clone(), id);
+        self.id_strs.push(id_str);
 
         self.values.push(value);
-        id
+        Ok(id)
     }
 
-    /// Get an id based on the id string.
+    /// Get an id based on the id string. Panics if the id string is unknown.
     pub fn id(&self, id_str: &str) -> Id<T> {
-        self.ids[id_str]
+        self.try_id(id_str).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Get an id based on the id string.
+    pub fn try_id(&self, id_str: &str) -> Result<Id<T>, ModelError> {
+        self.find(id_str)
+            .ok_or_else(|| ModelError::UnknownId(id_str.to_string()))
+    }
+
+    /// Get the id string of an id.
+    pub fn id_str(&self, id: Id<T>) -> &str {
+        &self.id_strs[id.0]
+    }
+
+    /// Look up an id based on the id string, if it exists.
+    pub(crate) fn find(&self, id_str: &str) -> Option<Id<T>> {
+        self.ids.get(id_str).copied()
     }
 
     /// Get a reference to the value with the give index.
@@ -51,6 +76,20 @@ impl<T> Container<T> {
         &self.values[id.0]
     }
 
+    /// Get a reference to the value with the give index, if the index is valid.
+    pub fn try_get(&self, id: Id<T>) -> Option<&T> {
+        self.values.get(id.0)
+    }
+
+    /// Returns an error unless all ids refer to values of this container.
+    pub(crate) fn check(&self, ids: impl IntoIterator<Item = Id<T>>) -> Result<(), ModelError> {
+        if ids.into_iter().all(|id| self.try_get(id).is_some()) {
+            Ok(())
+        } else {
+            Err(ModelError::InvalidId)
+        }
+    }
+
     /// Get a mutable reference to the value with the give index.
     pub(crate) fn get_mut(&mut self, id: Id<T>) -> &mut T {
         &mut self.values[id.0]
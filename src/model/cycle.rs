@@ -0,0 +1,124 @@
+use super::{Id, Item, Model, Resource, Value};
+use std::{collections::HashMap, error::Error, fmt};
+
+/// Node in the graph of values, conditional items and resources.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Node {
+    Value(Id<Value>),
+    Item(Id<Item>),
+    Resource(Id<Resource>),
+}
+
+/// Error when a rule would make a value depend on itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CycleError {
+    path: Vec<String>,
+}
+
+impl CycleError {
+    /// Id strings of the values and items that form the cycle. The first and last entries are
+    /// the same.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "dependency cycle: {}", self.path.join(" -> "))
+    }
+}
+
+impl Error for CycleError {}
+
+impl Model {
+    pub(crate) fn successors(&self, node: Node) -> Vec<Node> {
+        match node {
+            Node::Value(id) => {
+                let value = self.values.get(id);
+                value
+                    .dependents
+                    .iter()
+                    .map(|&id| Node::Value(id))
+                    .chain(value.conditions.iter().map(|&id| Node::Item(id)))
+                    .chain(value.resources.iter().map(|&id| Node::Resource(id)))
+                    .collect()
+            }
+            Node::Item(id) => self
+                .items
+                .get(id)
+                .modifications
+                .keys()
+                .map(|&id| Node::Value(id))
+                .collect(),
+            Node::Resource(_) => Vec::new(),
+        }
+    }
+
+    fn id_str(&self, node: Node) -> String {
+        match node {
+            Node::Value(id) => self.values.id_str(id).to_string(),
+            Node::Item(id) => self.items.id_str(id).to_string(),
+            Node::Resource(id) => self.resources.id_str(id).to_string(),
+        }
+    }
+
+    /// Breadth-first search for a path from `from` to `to`, including both ends.
+    fn find_path(&self, from: Node, to: Node) -> Option<Vec<Node>> {
+        let mut parents = HashMap::new();
+        let mut todo = vec![from];
+
+        while !todo.is_empty() {
+            let mut next = Vec::new();
+
+            for node in todo {
+                for successor in self.successors(node) {
+                    if successor == from || parents.contains_key(&successor) {
+                        continue;
+                    }
+                    parents.insert(successor, node);
+
+                    if successor == to {
+                        let mut path = vec![to];
+                        while let Some(&parent) = parents.get(path.last().unwrap()) {
+                            path.push(parent);
+                        }
+                        path.reverse();
+                        return Some(path);
+                    }
+
+                    next.push(successor);
+                }
+            }
+
+            todo = next;
+        }
+
+        None
+    }
+
+    /// Check that adding edges from each of `sources` to `target` does not create a cycle.
+    pub(crate) fn check_cycles(
+        &self,
+        sources: impl IntoIterator<Item = Node>,
+        target: Node,
+    ) -> Result<(), CycleError> {
+        for source in sources {
+            let path = if source == target {
+                Some(vec![target])
+            } else {
+                self.find_path(target, source)
+            };
+
+            if let Some(path) = path {
+                let path = std::iter::once(source)
+                    .chain(path)
+                    .map(|node| self.id_str(node))
+                    .collect();
+                return Err(CycleError { path });
+            }
+        }
+
+        Ok(())
+    }
+}
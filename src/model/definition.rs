@@ -0,0 +1,713 @@
+//! Declarative model definitions for use with serde.
+//!
+//! Entities reference each other by their id strings, calculations are stored as formulas (see
+//! [`Calculation::parse`]).
+
+use super::{
+    Calculation, Choice, FrontEnd, Group, Id, Inventory, Item, Load, Model, ModelError,
+    Modification, Recovery, Resource, Roll, RollModifier, Selection, Slot, Stacking, Value,
+};
+use crate::dice::Dice;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModelDef {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    main_inventory: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rounds_per_minute: Option<u32>,
+    #[serde(default)]
+    values: Vec<ValueDef>,
+    #[serde(default)]
+    choices: Vec<ChoiceDef>,
+    #[serde(default)]
+    inventories: Vec<InventoryDef>,
+    #[serde(default)]
+    items: Vec<ItemDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    groups: Vec<GroupDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    rolls: Vec<RollDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    resources: Vec<ResourceDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    recoveries: Vec<RecoveryDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    slots: Vec<SlotDef>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ValueDef {
+    id: String,
+    #[serde(default)]
+    default: i32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    loads: Vec<LoadDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    front_end: Option<FrontEnd>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LoadDef {
+    inventory: String,
+    #[serde(default, skip_serializing_if = "is_fill")]
+    load: Load,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChoiceDef {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    front_end: Option<FrontEnd>,
+    #[serde(default)]
+    selections: Vec<SelectionDef>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SelectionDef {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    front_end: Option<FrontEnd>,
+    #[serde(default)]
+    modifications: Vec<ModificationDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    roll_modifiers: Vec<RollModifierDef>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModificationDef {
+    value: String,
+    #[serde(default)]
+    priority: u16,
+    formula: String,
+    #[serde(default, skip_serializing_if = "is_stack")]
+    stacking: Stacking,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InventoryDef {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    capacity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    slots: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weight_percent: Option<u16>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PhysicalDef {
+    size: u16,
+    stack_size: u16,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ItemDef {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inventory: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    condition: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    front_end: Option<FrontEnd>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    physical: Option<PhysicalDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    slots: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    charges: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    durability: Option<u32>,
+    #[serde(default)]
+    modifications: Vec<ModificationDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    roll_modifiers: Vec<RollModifierDef>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GroupDef {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    front_end: Option<FrontEnd>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    values: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    items: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    resources: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ResourceDef {
+    id: String,
+    maximum: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    front_end: Option<FrontEnd>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecoveryDef {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    front_end: Option<FrontEnd>,
+    #[serde(default)]
+    refreshes: Vec<RefreshDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    recharges: Vec<RechargeDef>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RefreshDef {
+    resource: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    amount: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RechargeDef {
+    item: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    amount: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SlotDef {
+    id: String,
+    count: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    front_end: Option<FrontEnd>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RollDef {
+    id: String,
+    dice: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bonus: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    front_end: Option<FrontEnd>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RollModifierDef {
+    roll: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bonus: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    advantage: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    disadvantage: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+fn is_fill(load: &Load) -> bool {
+    *load == Load::Fill
+}
+
+fn is_stack(stacking: &Stacking) -> bool {
+    *stacking == Stacking::Stack
+}
+
+impl Model {
+    fn formula(&self, calc: &Calculation) -> String {
+        calc.display(self).to_string()
+    }
+
+    fn modification_defs(
+        &self,
+        modifications: &HashMap<Id<Value>, Modification>,
+    ) -> Vec<ModificationDef> {
+        let mut modifications: Vec<_> = modifications.iter().collect();
+        modifications.sort_unstable_by_key(|(id, _)| id.0);
+
+        modifications
+            .into_iter()
+            .map(|(&id, modification)| ModificationDef {
+                value: self.values.id_str(id).to_string(),
+                priority: modification.priority(),
+                formula: modification
+                    .calculation()
+                    .display_modification(self, id)
+                    .to_string(),
+                stacking: modification.stacking().clone(),
+            })
+            .collect()
+    }
+
+    fn roll_modifier_defs(&self, modifiers: &[(Id<Roll>, RollModifier)]) -> Vec<RollModifierDef> {
+        modifiers
+            .iter()
+            .map(|(id, modifier)| RollModifierDef {
+                roll: self.rolls.id_str(*id).to_string(),
+                bonus: modifier.calculation().map(|calc| self.formula(calc)),
+                advantage: matches!(modifier, RollModifier::Advantage),
+                disadvantage: matches!(modifier, RollModifier::Disadvantage),
+            })
+            .collect()
+    }
+
+    fn to_def(&self) -> ModelDef {
+        ModelDef {
+            main_inventory: self
+                .main_inventory
+                .map(|id| self.inventories.id_str(id).to_string()),
+            rounds_per_minute: self.rounds_per_minute,
+            values: self
+                .values
+                .iter()
+                .map(|(id, value)| ValueDef {
+                    id: self.values.id_str(id).to_string(),
+                    default: value.default,
+                    dependencies: value
+                        .dependencies
+                        .iter()
+                        .map(|calc| self.formula(calc))
+                        .collect(),
+                    loads: value
+                        .loads
+                        .iter()
+                        .map(|&(inventory, load)| LoadDef {
+                            inventory: self.inventories.id_str(inventory).to_string(),
+                            load,
+                        })
+                        .collect(),
+                    front_end: value.front_end.clone(),
+                })
+                .collect(),
+            choices: self
+                .choices
+                .iter()
+                .map(|(id, choice)| ChoiceDef {
+                    id: self.choices.id_str(id).to_string(),
+                    front_end: choice.front_end.clone(),
+                    selections: choice
+                        .options
+                        .iter()
+                        .map(|selection| SelectionDef {
+                            front_end: selection.front_end.clone(),
+                            modifications: self.modification_defs(&selection.modifications),
+                            roll_modifiers: self.roll_modifier_defs(&selection.roll_modifiers),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            inventories: self
+                .inventories
+                .iter()
+                .map(|(id, inventory)| InventoryDef {
+                    id: self.inventories.id_str(id).to_string(),
+                    capacity: inventory.capacity.as_ref().map(|calc| self.formula(calc)),
+                    slots: inventory.slots.as_ref().map(|calc| self.formula(calc)),
+                    weight_percent: inventory.weight_percent,
+                })
+                .collect(),
+            items: self
+                .items
+                .iter()
+                .map(|(id, item)| ItemDef {
+                    id: self.items.id_str(id).to_string(),
+                    inventory: item
+                        .has_inventory
+                        .map(|id| self.inventories.id_str(id).to_string()),
+                    condition: item.condition.as_ref().map(|calc| self.formula(calc)),
+                    front_end: item.front_end.clone(),
+                    physical: item.physical.as_ref().map(|physical| PhysicalDef {
+                        size: physical.size,
+                        stack_size: physical.stack_size.get(),
+                    }),
+                    slots: item
+                        .slots
+                        .iter()
+                        .map(|&id| self.slots.id_str(id).to_string())
+                        .collect(),
+                    charges: item.charges,
+                    durability: item.durability,
+                    modifications: self.modification_defs(&item.modifications),
+                    roll_modifiers: self.roll_modifier_defs(&item.roll_modifiers),
+                })
+                .collect(),
+            groups: self
+                .groups
+                .iter()
+                .map(|(id, group)| GroupDef {
+                    id: self.groups.id_str(id).to_string(),
+                    front_end: group.front_end.clone(),
+                    values: group
+                        .values
+                        .iter()
+                        .map(|&id| self.values.id_str(id).to_string())
+                        .collect(),
+                    items: group
+                        .items
+                        .iter()
+                        .map(|&id| self.items.id_str(id).to_string())
+                        .collect(),
+                    resources: group
+                        .resources
+                        .iter()
+                        .map(|&id| self.resources.id_str(id).to_string())
+                        .collect(),
+                })
+                .collect(),
+            rolls: self
+                .rolls
+                .iter()
+                .map(|(id, roll)| RollDef {
+                    id: self.rolls.id_str(id).to_string(),
+                    dice: roll.dice.display(self).to_string(),
+                    bonus: roll.bonus.as_ref().map(|calc| self.formula(calc)),
+                    front_end: roll.front_end.clone(),
+                })
+                .collect(),
+            resources: self
+                .resources
+                .iter()
+                .map(|(id, resource)| ResourceDef {
+                    id: self.resources.id_str(id).to_string(),
+                    maximum: self.formula(&resource.maximum),
+                    front_end: resource.front_end.clone(),
+                })
+                .collect(),
+            recoveries: self
+                .recoveries
+                .iter()
+                .map(|(id, recovery)| RecoveryDef {
+                    id: self.recoveries.id_str(id).to_string(),
+                    front_end: recovery.front_end.clone(),
+                    refreshes: recovery
+                        .refreshes()
+                        .map(|(resource, amount)| RefreshDef {
+                            resource: self.resources.id_str(resource).to_string(),
+                            amount: amount.map(|calc| self.formula(calc)),
+                        })
+                        .collect(),
+                    recharges: recovery
+                        .recharges()
+                        .map(|(item, amount)| RechargeDef {
+                            item: self.items.id_str(item).to_string(),
+                            amount: amount.map(|calc| self.formula(calc)),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            slots: self
+                .slots
+                .iter()
+                .map(|(id, slot)| SlotDef {
+                    id: self.slots.id_str(id).to_string(),
+                    count: slot.count,
+                    front_end: slot.front_end.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Prefix an error with the entity it occured in.
+fn context<T>(kind: &str, id: &str, result: Result<T, ModelError>) -> Result<T, String> {
+    result.map_err(|err| format!("{} `{}`: {}", kind, id, err))
+}
+
+fn modifications(
+    model: &Model,
+    defs: Vec<ModificationDef>,
+) -> Result<Vec<(Id<Value>, Modification)>, ModelError> {
+    defs.into_iter()
+        .map(|def| {
+            let id = model.values.try_id(&def.value)?;
+            let calc = Calculation::parse(&def.formula, model)?;
+            let modification = Modification::new(def.priority, calc).set_stacking(def.stacking);
+            Ok((id, modification))
+        })
+        .collect()
+}
+
+fn roll_modifiers(
+    model: &Model,
+    defs: Vec<RollModifierDef>,
+) -> Result<Vec<(Id<Roll>, RollModifier)>, ModelError> {
+    let mut result = Vec::new();
+    for def in defs {
+        let id = model.rolls.try_id(&def.roll)?;
+        if let Some(bonus) = def.bonus {
+            let calc = Calculation::parse(&bonus, model)?;
+            result.push((id, RollModifier::Bonus(calc)));
+        }
+        if def.advantage {
+            result.push((id, RollModifier::Advantage));
+        }
+        if def.disadvantage {
+            result.push((id, RollModifier::Disadvantage));
+        }
+    }
+    Ok(result)
+}
+
+impl RollDef {
+    fn build(self, model: &mut Model) -> Result<(), ModelError> {
+        let mut roll = Roll::new(Dice::parse(&self.dice, model)?);
+        roll.front_end = self.front_end;
+        if let Some(bonus) = self.bonus {
+            roll = roll.bonus(Calculation::parse(&bonus, model)?);
+        }
+
+        model.try_add_roll(self.id, roll)?;
+        Ok(())
+    }
+}
+
+impl ResourceDef {
+    fn build(self, model: &mut Model) -> Result<(), ModelError> {
+        let mut resource = Resource::new(Calculation::parse(&self.maximum, model)?);
+        resource.front_end = self.front_end;
+
+        model.try_add_resource(self.id, resource)?;
+        Ok(())
+    }
+}
+
+impl RecoveryDef {
+    fn build(self, model: &mut Model) -> Result<(), ModelError> {
+        let mut recovery = Recovery::new();
+        recovery.front_end = self.front_end;
+        for def in self.refreshes {
+            let resource = model.resources.try_id(&def.resource)?;
+            recovery = match def.amount {
+                Some(amount) => recovery.refresh_by(resource, Calculation::parse(&amount, model)?),
+                None => recovery.refresh(resource),
+            };
+        }
+        for def in self.recharges {
+            let item = model.items.try_id(&def.item)?;
+            recovery = match def.amount {
+                Some(amount) => recovery.recharge_by(item, Calculation::parse(&amount, model)?),
+                None => recovery.recharge(item),
+            };
+        }
+
+        model.try_add_recovery(self.id, recovery)?;
+        Ok(())
+    }
+}
+
+impl InventoryDef {
+    fn build(self, model: &mut Model) -> Result<(), ModelError> {
+        let mut inventory = Inventory::new();
+        if let Some(capacity) = self.capacity {
+            inventory = inventory.capacity(Calculation::parse(&capacity, model)?);
+        }
+        if let Some(slots) = self.slots {
+            inventory = inventory.slots(Calculation::parse(&slots, model)?);
+        }
+        if let Some(percent) = self.weight_percent {
+            inventory = inventory.weight_percent(percent);
+        }
+
+        model.try_add_inventory(self.id, inventory)?;
+        Ok(())
+    }
+}
+
+impl ItemDef {
+    fn build(self, model: &mut Model) -> Result<(), ModelError> {
+        let mut item = Item::new();
+        item.front_end = self.front_end;
+        if let Some(physical) = self.physical {
+            item = item.try_set_physical(physical.size, physical.stack_size)?;
+        }
+        if let Some(inventory) = self.inventory {
+            item = item.set_inventory(model.inventories.try_id(&inventory)?);
+        }
+        if let Some(condition) = self.condition {
+            item = item.set_condition(Calculation::parse(&condition, model)?);
+        }
+        for slot in self.slots {
+            item = item.occupy(model.slots.try_id(&slot)?);
+        }
+        item.charges = self.charges;
+        item.durability = self.durability;
+
+        let id = model.try_add_item(self.id, item)?;
+        for (value, modification) in modifications(model, self.modifications)? {
+            model.try_add_modification(id, value, modification)?;
+        }
+        for (roll, modifier) in roll_modifiers(model, self.roll_modifiers)? {
+            model.try_add_roll_modifier(id, roll, modifier)?;
+        }
+        Ok(())
+    }
+}
+
+impl ChoiceDef {
+    fn build(self, model: &mut Model) -> Result<(), ModelError> {
+        let mut choice = Choice::new();
+        choice.front_end = self.front_end;
+        let id = model.try_add_choice(self.id, choice)?;
+
+        for def in self.selections {
+            let mods = modifications(model, def.modifications)?;
+            let mut selection = Selection::new(mods.into_iter());
+            selection.front_end = def.front_end;
+            for (roll, modifier) in roll_modifiers(model, def.roll_modifiers)? {
+                selection = selection.roll_modifier(roll, modifier);
+            }
+
+            model.try_add_selection(id, selection)?;
+        }
+        Ok(())
+    }
+}
+
+impl GroupDef {
+    fn build(self, model: &mut Model) -> Result<(), ModelError> {
+        let values = self
+            .values
+            .iter()
+            .map(|id| model.values.try_id(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let items = self
+            .items
+            .iter()
+            .map(|id| model.items.try_id(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let resources = self
+            .resources
+            .iter()
+            .map(|id| model.resources.try_id(id))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut group = Group::new()
+            .values(values)
+            .items(items)
+            .resources(resources);
+        group.front_end = self.front_end;
+
+        model.try_add_group(self.id, group)?;
+        Ok(())
+    }
+}
+
+impl ModelDef {
+    fn build(self) -> Result<Model, String> {
+        let mut model = Model::new();
+
+        for def in &self.values {
+            let mut value = Value::new(def.default);
+            value.front_end = def.front_end.clone();
+            context(
+                "value",
+                &def.id,
+                model.try_add_value(&def.id, value).map(|_| ()),
+            )?;
+        }
+
+        for def in self.inventories {
+            let id = def.id.clone();
+            context("inventory", &id, def.build(&mut model))?;
+        }
+
+        if let Some(id) = self.main_inventory {
+            let id = context("main inventory", &id, model.inventories.try_id(&id))?;
+            model.set_main_inventory(id);
+        }
+        model.rounds_per_minute = self.rounds_per_minute;
+
+        for def in self.values {
+            let id = model.values.id(&def.id);
+
+            for formula in def.dependencies {
+                let result = Calculation::parse(&formula, &model)
+                    .map_err(ModelError::from)
+                    .and_then(|calc| model.try_add_dependency(id, calc));
+                context("value", &def.id, result)?;
+            }
+
+            for load in def.loads {
+                let result = model
+                    .inventories
+                    .try_id(&load.inventory)
+                    .and_then(|inventory| model.try_add_load(id, inventory, load.load));
+                context("value", &def.id, result)?;
+            }
+        }
+
+        for def in self.resources {
+            let id = def.id.clone();
+            context("resource", &id, def.build(&mut model))?;
+        }
+
+        for def in self.rolls {
+            let id = def.id.clone();
+            context("roll", &id, def.build(&mut model))?;
+        }
+
+        for def in self.slots {
+            let mut slot = Slot::new(def.count);
+            slot.front_end = def.front_end;
+            context(
+                "slot",
+                &def.id,
+                model.try_add_slot(&def.id, slot).map(|_| ()),
+            )?;
+        }
+
+        for def in self.items {
+            let id = def.id.clone();
+            context("item", &id, def.build(&mut model))?;
+        }
+
+        // Recoveries can recharge items
+        for def in self.recoveries {
+            let id = def.id.clone();
+            context("recovery", &id, def.build(&mut model))?;
+        }
+
+        for def in self.choices {
+            let id = def.id.clone();
+            context("choice", &id, def.build(&mut model))?;
+        }
+
+        for def in self.groups {
+            let id = def.id.clone();
+            context("group", &id, def.build(&mut model))?;
+        }
+
+        Ok(model)
+    }
+}
+
+impl Serialize for Model {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_def().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Model {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ModelDef::deserialize(deserializer)?
+            .build()
+            .map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,68 @@
+use super::{CycleError, ParseError};
+use crate::dice::DiceError;
+use std::{error::Error, fmt};
+
+/// Error when building a model.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModelError {
+    /// An id string is already used by another entity of the same kind.
+    DuplicateId(String),
+    /// An id string does not refer to any entity.
+    UnknownId(String),
+    /// An id does not refer to any entity of this model, e.g. because it comes from another one.
+    InvalidId,
+    /// A physical item was declared with a stack size of zero.
+    ZeroStackSize,
+    /// A calculation that will be evaluated still contains placeholders.
+    Placeholder,
+    /// The rule would make a value depend on itself.
+    Cycle(CycleError),
+    /// A formula could not be parsed.
+    Parse(ParseError),
+    /// A dice expression could not be parsed.
+    Dice(DiceError),
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DuplicateId(id) => write!(f, "duplicate id `{}`", id),
+            Self::UnknownId(id) => write!(f, "unknown id `{}`", id),
+            Self::InvalidId => write!(f, "id does not belong to this model"),
+            Self::ZeroStackSize => write!(f, "stack size can not be zero"),
+            Self::Placeholder => write!(f, "calculation contains placeholders"),
+            Self::Cycle(err) => write!(f, "{}", err),
+            Self::Parse(err) => write!(f, "{}", err),
+            Self::Dice(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for ModelError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Cycle(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::Dice(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<CycleError> for ModelError {
+    fn from(err: CycleError) -> Self {
+        Self::Cycle(err)
+    }
+}
+
+impl From<ParseError> for ModelError {
+    fn from(err: ParseError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<DiceError> for ModelError {
+    fn from(err: DiceError) -> Self {
+        Self::Dice(err)
+    }
+}
@@ -0,0 +1,59 @@
+use super::{FrontEnd, Id, Item, Resource, Value};
+
+/// A set of values, items and resources that can be observed together, e.g. "abilities".
+#[derive(Default)]
+pub struct Group {
+    /// Front end data
+    pub front_end: Option<FrontEnd>,
+
+    pub(crate) values: Vec<Id<Value>>,
+    pub(crate) items: Vec<Id<Item>>,
+    pub(crate) resources: Vec<Id<Resource>>,
+}
+
+impl Group {
+    /// Create a new empty group.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add front end metadata.
+    pub fn front_end(mut self, front_end: FrontEnd) -> Self {
+        self.front_end = Some(front_end);
+        self
+    }
+
+    /// Add values to the group.
+    pub fn values(mut self, values: impl IntoIterator<Item = Id<Value>>) -> Self {
+        self.values.extend(values);
+        self
+    }
+
+    /// Add items to the group. Changes to equipped counts and inventory contents of these items
+    /// are part of the group.
+    pub fn items(mut self, items: impl IntoIterator<Item = Id<Item>>) -> Self {
+        self.items.extend(items);
+        self
+    }
+
+    /// Add resources to the group.
+    pub fn resources(mut self, resources: impl IntoIterator<Item = Id<Resource>>) -> Self {
+        self.resources.extend(resources);
+        self
+    }
+
+    /// Returns true if the value is part of the group.
+    pub fn contains_value(&self, id: Id<Value>) -> bool {
+        self.values.contains(&id)
+    }
+
+    /// Returns true if the item is part of the group.
+    pub fn contains_item(&self, id: Id<Item>) -> bool {
+        self.items.contains(&id)
+    }
+
+    /// Returns true if the resource is part of the group.
+    pub fn contains_resource(&self, id: Id<Resource>) -> bool {
+        self.resources.contains(&id)
+    }
+}
@@ -1,10 +1,25 @@
-use super::{Calculation, IntoCalculation};
+use super::{Calculation, Id, IntoCalculation, Value};
+
+/// Measure of how much an inventory is used, see [`Model::add_load`](super::Model::add_load).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Load {
+    /// Total size of the content, including the weighted content of nested inventories.
+    #[default]
+    Fill,
+    /// Number of occupied slots.
+    Slots,
+}
 
 /// Represents an inventory type.
 #[derive(Default)]
 pub struct Inventory {
     pub(crate) capacity: Option<Calculation>,
     pub(crate) slots: Option<Calculation>,
+    pub(crate) weight_percent: Option<u16>,
+
+    pub(crate) loaded: Vec<Id<Value>>,
 }
 
 impl Inventory {
@@ -24,4 +39,17 @@ impl Inventory {
         self.slots = Some(calc.into_calc());
         self
     }
+
+    /// Count the content of inventories of this type with the given percentage towards the
+    /// inventory holding the owning item. Defaults to 100, use 0 for a bag of holding that always
+    /// has the size of the item itself.
+    pub fn weight_percent(mut self, percent: u16) -> Self {
+        self.weight_percent = Some(percent);
+        self
+    }
+
+    /// Percentage of the content that counts towards the inventory holding the owning item.
+    pub fn content_weight(&self) -> u16 {
+        self.weight_percent.unwrap_or(100)
+    }
 }
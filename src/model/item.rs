@@ -1,4 +1,7 @@
-use super::{Calculation, FrontEnd, Id, IntoCalculation, Inventory, Modification, Value};
+use super::{
+    Calculation, FrontEnd, Id, IntoCalculation, Inventory, ModelError, Modification, Roll,
+    RollModifier, Slot, Value,
+};
 use std::{collections::HashMap, num::NonZeroU16};
 
 pub(crate) struct Physical {
@@ -15,9 +18,13 @@ pub struct Item {
 
     pub(crate) physical: Option<Physical>,
     pub(crate) has_inventory: Option<Id<Inventory>>,
+    pub(crate) slots: Vec<Id<Slot>>,
+    pub(crate) charges: Option<u32>,
+    pub(crate) durability: Option<u32>,
 
     pub(crate) condition: Option<Calculation>,
     pub(crate) modifications: HashMap<Id<Value>, Modification>,
+    pub(crate) roll_modifiers: Vec<(Id<Roll>, RollModifier)>,
 }
 
 impl Item {
@@ -38,11 +45,18 @@ impl Item {
         self
     }
 
-    /// Declare this to be a physical item that can be put into inventories.
-    pub fn set_physical(mut self, size: u16, stack_size: u16) -> Self {
-        let stack_size = NonZeroU16::new(stack_size).unwrap();
+    /// Declare this to be a physical item that can be put into inventories. Panics if
+    /// `stack_size` is zero.
+    pub fn set_physical(self, size: u16, stack_size: u16) -> Self {
+        self.try_set_physical(size, stack_size)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Declare this to be a physical item, unless `stack_size` is zero.
+    pub fn try_set_physical(mut self, size: u16, stack_size: u16) -> Result<Self, ModelError> {
+        let stack_size = NonZeroU16::new(stack_size).ok_or(ModelError::ZeroStackSize)?;
         self.physical = Some(Physical { size, stack_size });
-        self
+        Ok(self)
     }
 
     /// Change the invetory type to use with this item.
@@ -50,4 +64,38 @@ impl Item {
         self.has_inventory = Some(id);
         self
     }
+
+    /// Occupy an equipment slot while equipped. Call multiple times for items that need more
+    /// than one, e.g. two-handed weapons.
+    pub fn occupy(mut self, id: Id<Slot>) -> Self {
+        self.slots.push(id);
+        self
+    }
+
+    /// Give every instance of this item its own charges, starting at `maximum`.
+    pub fn set_charges(mut self, maximum: u32) -> Self {
+        self.charges = Some(maximum);
+        self
+    }
+
+    /// Give every instance of this item its own durability, starting at `maximum`.
+    pub fn set_durability(mut self, maximum: u32) -> Self {
+        self.durability = Some(maximum);
+        self
+    }
+
+    /// Maximum charges of an instance, if the item has charges.
+    pub fn charges(&self) -> Option<u32> {
+        self.charges
+    }
+
+    /// Maximum durability of an instance, if the item has durability.
+    pub fn durability(&self) -> Option<u32> {
+        self.durability
+    }
+
+    /// Equipment slots occupied by this item.
+    pub fn slots(&self) -> &[Id<Slot>] {
+        &self.slots
+    }
 }
@@ -1,9 +1,32 @@
 use super::{Calculation, Id, Value};
 
+/// How a modification combines with other modifications of the same value.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Stacking {
+    /// Applied once for every equipped copy of its source.
+    #[default]
+    Stack,
+    /// Applied once, no matter how often its source is equipped.
+    Once,
+    /// Only the highest bonus of all modifications with the same bonus type applies, e.g.
+    /// armor, shield or enhancement bonuses. The bonus is the difference the calculation makes
+    /// to the value before the first modification of the type. The highest bonus is applied at
+    /// its own priority.
+    Typed(String),
+    /// Raises the value to at least the result of the calculation.
+    AtLeast,
+    /// Lowers the value to at most the result of the calculation.
+    AtMost,
+}
+
 /// Represents a modification.
+#[derive(Debug)]
 pub struct Modification {
     priority: u16,
     calculation: Calculation,
+    stacking: Stacking,
 }
 
 impl Modification {
@@ -12,13 +35,25 @@ impl Modification {
         Self {
             priority,
             calculation,
+            stacking: Stacking::Stack,
         }
     }
 
+    /// Change how the modification combines with others, see [`Stacking`].
+    pub fn set_stacking(mut self, stacking: Stacking) -> Self {
+        self.stacking = stacking;
+        self
+    }
+
     pub(crate) fn set_value(&mut self, id: Id<Value>) {
         self.calculation.replace_with_value(id);
     }
 
+    /// Values other than the modified one that the calculation reads.
+    pub(crate) fn inputs(&self, id: Id<Value>) -> impl Iterator<Item = Id<Value>> + '_ {
+        self.calculation.values().filter(move |&input| input != id)
+    }
+
     /// Retrive the calculation.
     pub fn calculation(&self) -> &Calculation {
         &self.calculation
@@ -28,4 +63,9 @@ impl Modification {
     pub fn priority(&self) -> u16 {
         self.priority
     }
+
+    /// Retrieve the stacking behavior.
+    pub fn stacking(&self) -> &Stacking {
+        &self.stacking
+    }
 }
@@ -0,0 +1,107 @@
+use super::{cycle::Node, Model};
+use std::collections::VecDeque;
+
+/// Topological order of values, items and resources, with the successors of every node.
+pub(crate) struct Order {
+    nodes: Vec<Node>,
+    successors: Vec<Vec<usize>>,
+    /// Rank by index, with values before items before resources.
+    ranks: Vec<usize>,
+    offsets: Offsets,
+}
+
+/// Index of the first item and resource.
+#[derive(Clone, Copy)]
+struct Offsets {
+    items: usize,
+    resources: usize,
+}
+
+impl Offsets {
+    fn index(self, node: Node) -> usize {
+        match node {
+            Node::Value(id) => id.0,
+            Node::Item(id) => self.items + id.0,
+            Node::Resource(id) => self.resources + id.0,
+        }
+    }
+}
+
+impl Order {
+    /// Nodes sorted so that every node comes after everything it depends on.
+    pub(crate) fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Ranks of the nodes that have to be recomputed when the node at `rank` changes.
+    pub(crate) fn successors(&self, rank: usize) -> &[usize] {
+        &self.successors[rank]
+    }
+
+    /// Position of a node in the order.
+    pub(crate) fn rank(&self, node: Node) -> usize {
+        self.ranks[self.offsets.index(node)]
+    }
+}
+
+impl Model {
+    /// Get the topological order of the model, computing it on first use.
+    pub(crate) fn order(&self) -> &Order {
+        self.order.get_or_init(|| self.compute_order())
+    }
+
+    fn compute_order(&self) -> Order {
+        let nodes: Vec<_> = self
+            .values
+            .iter()
+            .map(|(id, _)| Node::Value(id))
+            .chain(self.items.iter().map(|(id, _)| Node::Item(id)))
+            .chain(self.resources.iter().map(|(id, _)| Node::Resource(id)))
+            .collect();
+        let items = self.values.iter().count();
+        let offsets = Offsets {
+            items,
+            resources: items + self.items.iter().count(),
+        };
+
+        let successors: Vec<Vec<usize>> = nodes
+            .iter()
+            .map(|&node| {
+                let successors = self.successors(node).into_iter();
+                successors.map(|node| offsets.index(node)).collect()
+            })
+            .collect();
+
+        let mut incoming = vec![0; nodes.len()];
+        for &successor in successors.iter().flatten() {
+            incoming[successor] += 1;
+        }
+
+        // Kahn's algorithm, the model is guaranteed to be acyclic
+        let mut todo: VecDeque<_> = (0..nodes.len()).filter(|&i| incoming[i] == 0).collect();
+        let mut ranks = vec![0; nodes.len()];
+        let mut sorted = Vec::with_capacity(nodes.len());
+        while let Some(i) = todo.pop_front() {
+            ranks[i] = sorted.len();
+            sorted.push(i);
+
+            for &successor in &successors[i] {
+                incoming[successor] -= 1;
+                if incoming[successor] == 0 {
+                    todo.push_back(successor);
+                }
+            }
+        }
+        debug_assert_eq!(sorted.len(), nodes.len());
+
+        Order {
+            nodes: sorted.iter().map(|&i| nodes[i]).collect(),
+            successors: sorted
+                .iter()
+                .map(|&i| successors[i].iter().map(|&s| ranks[s]).collect())
+                .collect(),
+            ranks,
+            offsets,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use super::{Calculation, FrontEnd, Id, IntoCalculation, Item, Resource};
+
+/// Named recovery event like a short rest, a long rest or a new day. Refreshes resources and
+/// recharges stored items with charges (see [`Item::set_charges`]).
+#[derive(Default)]
+pub struct Recovery {
+    /// Front end data
+    pub front_end: Option<FrontEnd>,
+
+    /// Resources to refresh in order, either fully or by a calculated amount.
+    pub(crate) refreshes: Vec<(Id<Resource>, Option<Calculation>)>,
+    /// Items to recharge in order, either fully or by a calculated amount.
+    pub(crate) recharges: Vec<(Id<Item>, Option<Calculation>)>,
+}
+
+impl Recovery {
+    /// Create a new recovery event that does not refresh anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add front end metadata.
+    pub fn front_end(mut self, front_end: FrontEnd) -> Self {
+        self.front_end = Some(front_end);
+        self
+    }
+
+    /// Refill a resource to its maximum.
+    pub fn refresh(mut self, resource: Id<Resource>) -> Self {
+        self.refreshes.push((resource, None));
+        self
+    }
+
+    /// Refill a resource by a calculated amount, up to its maximum. Negative amounts restore
+    /// nothing.
+    pub fn refresh_by(mut self, resource: Id<Resource>, amount: impl IntoCalculation) -> Self {
+        self.refreshes.push((resource, Some(amount.into_calc())));
+        self
+    }
+
+    /// Refreshed resources with their amounts, `None` meaning a full refresh.
+    pub fn refreshes(&self) -> impl Iterator<Item = (Id<Resource>, Option<&Calculation>)> {
+        self.refreshes.iter().map(|(id, calc)| (*id, calc.as_ref()))
+    }
+
+    /// Restore all charges of every stored instance of an item.
+    pub fn recharge(mut self, item: Id<Item>) -> Self {
+        self.recharges.push((item, None));
+        self
+    }
+
+    /// Restore a calculated number of charges of every stored instance of an item, up to its
+    /// maximum. Negative amounts restore nothing.
+    pub fn recharge_by(mut self, item: Id<Item>, amount: impl IntoCalculation) -> Self {
+        self.recharges.push((item, Some(amount.into_calc())));
+        self
+    }
+
+    /// Recharged items with their amounts, `None` meaning a full recharge.
+    pub fn recharges(&self) -> impl Iterator<Item = (Id<Item>, Option<&Calculation>)> {
+        self.recharges.iter().map(|(id, calc)| (*id, calc.as_ref()))
+    }
+}
@@ -0,0 +1,31 @@
+use super::{Calculation, FrontEnd, IntoCalculation};
+
+/// Depleting pool like hit points or spell slots. The maximum is computed, the current amount
+/// is stored on the character.
+pub struct Resource {
+    /// Front end data
+    pub front_end: Option<FrontEnd>,
+
+    pub(crate) maximum: Calculation,
+}
+
+impl Resource {
+    /// Create a new resource with a calculated maximum.
+    pub fn new(maximum: impl IntoCalculation) -> Self {
+        Self {
+            front_end: None,
+            maximum: maximum.into_calc(),
+        }
+    }
+
+    /// Add front end metadata.
+    pub fn front_end(mut self, front_end: FrontEnd) -> Self {
+        self.front_end = Some(front_end);
+        self
+    }
+
+    /// The calculation of the maximum.
+    pub fn maximum(&self) -> &Calculation {
+        &self.maximum
+    }
+}
@@ -0,0 +1,64 @@
+use super::{Calculation, Choice, FrontEnd, Id, IntoCalculation, Item};
+use crate::dice::Dice;
+
+/// Named check like an attack roll, a saving throw or a skill check.
+pub struct Roll {
+    /// Front end data
+    pub front_end: Option<FrontEnd>,
+
+    pub(crate) dice: Dice,
+    pub(crate) bonus: Option<Calculation>,
+
+    pub(crate) modifying_items: Vec<Id<Item>>,
+    pub(crate) modifying_choices: Vec<Id<Choice>>,
+}
+
+impl Roll {
+    /// Create a new roll of a dice expression.
+    pub fn new(dice: Dice) -> Self {
+        Self {
+            front_end: None,
+            dice,
+            bonus: None,
+            modifying_items: Vec::new(),
+            modifying_choices: Vec::new(),
+        }
+    }
+
+    /// Add front end metadata.
+    pub fn front_end(mut self, front_end: FrontEnd) -> Self {
+        self.front_end = Some(front_end);
+        self
+    }
+
+    /// Add a static bonus to every roll.
+    pub fn bonus(mut self, bonus: impl IntoCalculation) -> Self {
+        self.bonus = Some(bonus.into_calc());
+        self
+    }
+
+    /// The dice expression to roll.
+    pub fn dice(&self) -> &Dice {
+        &self.dice
+    }
+}
+
+/// Change to a roll granted by an item or a selection.
+#[derive(Debug)]
+pub enum RollModifier {
+    /// Add the result of a calculation.
+    Bonus(Calculation),
+    /// Roll dice twice and keep the higher result.
+    Advantage,
+    /// Roll dice twice and keep the lower result.
+    Disadvantage,
+}
+
+impl RollModifier {
+    pub(crate) fn calculation(&self) -> Option<&Calculation> {
+        match self {
+            Self::Bonus(calc) => Some(calc),
+            _ => None,
+        }
+    }
+}
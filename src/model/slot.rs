@@ -0,0 +1,33 @@
+use super::{FrontEnd, Id, Item};
+
+/// Equipment slot like head, body or ring. Items occupying slots can only be equipped while they
+/// are carried and the slot has room for them.
+pub struct Slot {
+    /// Front end data
+    pub front_end: Option<FrontEnd>,
+
+    pub(crate) count: u16,
+    pub(crate) items: Vec<Id<Item>>,
+}
+
+impl Slot {
+    /// Create a new slot that can hold `count` items at once, e.g. two for rings.
+    pub fn new(count: u16) -> Self {
+        Self {
+            front_end: None,
+            count,
+            items: Vec::new(),
+        }
+    }
+
+    /// Add front end metadata.
+    pub fn front_end(mut self, front_end: FrontEnd) -> Self {
+        self.front_end = Some(front_end);
+        self
+    }
+
+    /// Number of items the slot can hold at once.
+    pub fn count(&self) -> u16 {
+        self.count
+    }
+}
@@ -0,0 +1,241 @@
+use super::{Calculation, Model};
+use std::fmt;
+
+/// How severe a finding of [`Model::validate`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The model works, but probably not as intended.
+    Warning,
+    /// Characters of the model can panic or compute meaningless results.
+    Error,
+}
+
+/// Problem found by [`Model::validate`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Issue {
+    /// A calculation still contains placeholders.
+    Placeholder,
+    /// A calculation divides by a constant zero.
+    DivisionByZero,
+    /// A calculation takes the remainder of a division by a value that might be zero.
+    PossibleDivisionByZero,
+    /// An item condition does not depend on any values.
+    ConstantCondition(i32),
+    /// An item has an inventory but is not physical, so it can not be stored.
+    InventoryNotPhysical,
+    /// An item with an inventory is applied conditionally.
+    ConditionalInventory,
+    /// An item occupies equipment slots but is not physical, so it can never be carried.
+    SlotNotPhysical,
+    /// There are physical items, but no main inventory to store them in.
+    MissingMainInventory,
+    /// A choice has no selections to choose from.
+    EmptyChoice,
+    /// A recovery recharges an item without charges.
+    NoCharges,
+    /// A choice has this many selections past index `u16::MAX`, which can not be selected.
+    UnreachableSelections(usize),
+}
+
+impl Issue {
+    /// Severity of this kind of problem.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::PossibleDivisionByZero
+            | Self::ConstantCondition(_)
+            | Self::EmptyChoice
+            | Self::UnreachableSelections(_)
+            | Self::NoCharges => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Placeholder => write!(f, "calculation contains placeholders"),
+            Self::DivisionByZero => write!(f, "calculation divides by zero"),
+            Self::PossibleDivisionByZero => write!(f, "remainder of a division by a value"),
+            Self::ConstantCondition(c) => write!(f, "condition is always {}", c),
+            Self::InventoryNotPhysical => write!(f, "item has an inventory but is not physical"),
+            Self::ConditionalInventory => write!(f, "item has an inventory but is conditional"),
+            Self::SlotNotPhysical => write!(f, "item occupies slots but is not physical"),
+            Self::MissingMainInventory => write!(f, "physical items but no main inventory"),
+            Self::EmptyChoice => write!(f, "choice has no selections"),
+            Self::NoCharges => write!(f, "recharged item has no charges"),
+            Self::UnreachableSelections(n) => write!(f, "{} selections can not be selected", n),
+        }
+    }
+}
+
+/// Finding of [`Model::validate`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    /// Description of the entity, e.g. ``item `chestplate` ``. Empty for the model itself.
+    pub entity: String,
+    /// The problem.
+    pub issue: Issue,
+}
+
+impl Diagnostic {
+    /// Severity of the problem.
+    pub fn severity(&self) -> Severity {
+        self.issue.severity()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity() {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        if self.entity.is_empty() {
+            write!(f, "{}: {}", severity, self.issue)
+        } else {
+            write!(f, "{}: {}: {}", severity, self.entity, self.issue)
+        }
+    }
+}
+
+struct Validation(Vec<Diagnostic>);
+
+impl Validation {
+    fn report(&mut self, entity: impl ToString, issue: Issue) {
+        self.0.push(Diagnostic {
+            entity: entity.to_string(),
+            issue,
+        });
+    }
+
+    fn check_calculation(&mut self, entity: &str, calc: &Calculation) {
+        if calc.has_placeholders() {
+            self.report(entity, Issue::Placeholder);
+        }
+
+        for (remainder, divisor) in calc.divisors() {
+            match divisor {
+                Some(0) => self.report(entity, Issue::DivisionByZero),
+                None if remainder => self.report(entity, Issue::PossibleDivisionByZero),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Model {
+    /// Check the model for problems that would surface when using characters, ordered by entity.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut validation = Validation(Vec::new());
+
+        for (id, value) in self.values.iter() {
+            let entity = format!("value `{}`", self.values.id_str(id));
+            for calc in &value.dependencies {
+                validation.check_calculation(&entity, calc);
+            }
+        }
+
+        for (id, choice) in self.choices.iter() {
+            let entity = format!("choice `{}`", self.choices.id_str(id));
+            if choice.options.is_empty() {
+                validation.report(&entity, Issue::EmptyChoice);
+            }
+            let reachable = usize::from(u16::MAX) + 1;
+            if choice.options.len() > reachable {
+                let unreachable = choice.options.len() - reachable;
+                validation.report(&entity, Issue::UnreachableSelections(unreachable));
+            }
+
+            for (idx, selection) in choice.options.iter().enumerate() {
+                let entity = format!("{} selection {}", entity, idx);
+                for modification in selection.modifications.values() {
+                    validation.check_calculation(&entity, modification.calculation());
+                }
+                for (_, modifier) in &selection.roll_modifiers {
+                    if let Some(calc) = modifier.calculation() {
+                        validation.check_calculation(&entity, calc);
+                    }
+                }
+            }
+        }
+
+        for (id, inventory) in self.inventories.iter() {
+            let entity = format!("inventory `{}`", self.inventories.id_str(id));
+            for calc in inventory.capacity.iter().chain(&inventory.slots) {
+                validation.check_calculation(&entity, calc);
+            }
+        }
+
+        for (id, roll) in self.rolls.iter() {
+            let entity = format!("roll `{}`", self.rolls.id_str(id));
+            if let Some(bonus) = &roll.bonus {
+                validation.check_calculation(&entity, bonus);
+            }
+        }
+
+        for (id, resource) in self.resources.iter() {
+            let entity = format!("resource `{}`", self.resources.id_str(id));
+            validation.check_calculation(&entity, &resource.maximum);
+        }
+
+        for (id, recovery) in self.recoveries.iter() {
+            let entity = format!("recovery `{}`", self.recoveries.id_str(id));
+            for (_, amount) in recovery.refreshes() {
+                if let Some(amount) = amount {
+                    validation.check_calculation(&entity, amount);
+                }
+            }
+            for (item, amount) in recovery.recharges() {
+                if self.items.get(item).charges().is_none() {
+                    validation.report(&entity, Issue::NoCharges);
+                }
+                if let Some(amount) = amount {
+                    validation.check_calculation(&entity, amount);
+                }
+            }
+        }
+
+        let mut physical = false;
+        for (id, item) in self.items.iter() {
+            let entity = format!("item `{}`", self.items.id_str(id));
+            physical |= item.physical.is_some();
+
+            if item.has_inventory.is_some() {
+                if item.physical.is_none() {
+                    validation.report(&entity, Issue::InventoryNotPhysical);
+                }
+                if item.condition.is_some() {
+                    validation.report(&entity, Issue::ConditionalInventory);
+                }
+            }
+
+            if !item.slots.is_empty() && item.physical.is_none() && item.condition.is_none() {
+                validation.report(&entity, Issue::SlotNotPhysical);
+            }
+
+            if let Some(condition) = &item.condition {
+                validation.check_calculation(&entity, condition);
+                if let Some(c) = condition.constant() {
+                    validation.report(&entity, Issue::ConstantCondition(c));
+                }
+            }
+
+            for modification in item.modifications.values() {
+                validation.check_calculation(&entity, modification.calculation());
+            }
+            for (_, modifier) in &item.roll_modifiers {
+                if let Some(calc) = modifier.calculation() {
+                    validation.check_calculation(&entity, calc);
+                }
+            }
+        }
+
+        if physical && self.main_inventory.is_none() {
+            validation.report("", Issue::MissingMainInventory);
+        }
+
+        validation.0
+    }
+}
@@ -1,4 +1,4 @@
-use super::{Calculation, Choice, FrontEnd, Id, Item};
+use super::{Calculation, Choice, FrontEnd, Id, Inventory, Item, Load, Resource};
 
 /// A value in the character sheet.
 pub struct Value {
@@ -8,10 +8,12 @@ pub struct Value {
     pub(crate) default: i32,
 
     pub(crate) dependencies: Vec<Calculation>,
+    pub(crate) loads: Vec<(Id<Inventory>, Load)>,
     pub(crate) modifying_items: Vec<Id<Item>>,
     pub(crate) modifying_choices: Vec<Id<Choice>>,
     pub(crate) dependents: Vec<Id<Value>>,
     pub(crate) conditions: Vec<Id<Item>>,
+    pub(crate) resources: Vec<Id<Resource>>,
 }
 
 impl Value {
@@ -22,10 +24,12 @@ impl Value {
             default,
 
             dependencies: Vec::new(),
+            loads: Vec::new(),
             modifying_items: Vec::new(),
             modifying_choices: Vec::new(),
             dependents: Vec::new(),
             conditions: Vec::new(),
+            resources: Vec::new(),
         }
     }
 
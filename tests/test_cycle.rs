@@ -0,0 +1,135 @@
+use charsheet::model::*;
+use charsheet::Character;
+
+fn path(err: &ModelError) -> &[String] {
+    match err {
+        ModelError::Cycle(cycle) => cycle.path(),
+        err => panic!("unexpected error {}", err),
+    }
+}
+
+#[test]
+fn self_dependency() {
+    let mut model = Model::new();
+    let strength = model.add_value("strength", Value::new(0));
+
+    let err = model
+        .try_add_dependency(strength, strength + 1)
+        .unwrap_err();
+    assert_eq!(path(&err), ["strength", "strength"]);
+}
+
+#[test]
+fn dependency_cycle() {
+    let mut model = Model::new();
+    let a = model.add_value("a", Value::new(0));
+    let b = model.add_value("b", Value::new(0));
+    let c = model.add_value("c", Value::new(0));
+
+    model.add_dependency(b, a * 2);
+    model.add_dependency(c, b + 1);
+
+    let err = model.try_add_dependency(a, c - 1).unwrap_err();
+    assert_eq!(path(&err), ["c", "a", "b", "c"]);
+    assert_eq!(err.to_string(), "dependency cycle: c -> a -> b -> c");
+
+    // The rejected dependency is not part of the model
+    let mut character = Character::new(&model);
+    character.set_base(a, 1);
+    assert_eq!(character.get(c), 3);
+}
+
+#[test]
+#[should_panic(expected = "dependency cycle: b -> a -> b")]
+fn dependency_cycle_panics() {
+    let mut model = Model::new();
+    let a = model.add_value("a", Value::new(0));
+    let b = model.add_value("b", Value::new(0));
+
+    model.add_dependency(b, Calculation::from(a));
+    model.add_dependency(a, Calculation::from(b));
+}
+
+#[test]
+fn modification_cycle() {
+    let mut model = Model::new();
+    let armor = model.add_value("armor", Value::new(10));
+    let dexterity = model.add_value("dexterity", Value::new(2));
+    let shield = model.add_item("shield", Item::new());
+    let boots = model.add_item("boots", Item::new());
+
+    model.add_modification(
+        shield,
+        armor,
+        Modification::new(0, Calculation::placeholder() + dexterity),
+    );
+    let err = model
+        .try_add_modification(
+            boots,
+            dexterity,
+            Modification::new(0, Calculation::placeholder() + armor),
+        )
+        .unwrap_err();
+    assert_eq!(path(&err), ["armor", "dexterity", "armor"]);
+}
+
+#[test]
+fn modification_inputs_update() {
+    let mut model = Model::new();
+    let armor = model.add_value("armor", Value::new(10));
+    let dexterity = model.add_value("dexterity", Value::new(2));
+    let shield = model.add_item("shield", Item::new());
+    model.add_modification(
+        shield,
+        armor,
+        Modification::new(0, Calculation::placeholder() + dexterity),
+    );
+
+    let mut character = Character::new(&model);
+    character.equip(shield).unwrap();
+    assert_eq!(character.get(armor), 12);
+    character.set_base(dexterity, 4);
+    assert_eq!(character.get(armor), 14);
+}
+
+#[test]
+fn condition_cycle() {
+    let mut model = Model::new();
+    let burden = model.add_value("burden", Value::new(0));
+    let max_burden = model.add_value("max_burden", Value::new(20));
+
+    let overburdened = model.add_item(
+        "overburdened",
+        Item::new().set_condition(Calculation::gt(burden.into(), max_burden)),
+    );
+
+    let err = model
+        .try_add_modification(
+            overburdened,
+            max_burden,
+            Modification::new(0, Calculation::placeholder() - 5),
+        )
+        .unwrap_err();
+    assert_eq!(path(&err), ["overburdened", "max_burden", "overburdened"]);
+}
+
+#[test]
+fn selection_cycle() {
+    let mut model = Model::new();
+    let a = model.add_value("a", Value::new(0));
+    let b = model.add_value("b", Value::new(0));
+    let choice = model.add_choice("choice", Choice::new());
+
+    let selection = Selection::new(
+        vec![
+            (a, Modification::new(0, Calculation::placeholder() + b)),
+            (b, Modification::new(0, Calculation::placeholder() + a)),
+        ]
+        .into_iter(),
+    );
+    let err = model.try_add_selection(choice, selection).unwrap_err();
+    assert_eq!(path(&err).len(), 3);
+
+    // Edges of the rejected selection were removed again
+    model.add_dependency(a, b * 2);
+}
@@ -0,0 +1,209 @@
+#![cfg(feature = "serde")]
+
+use charsheet::dice::SplitMix64;
+use charsheet::model::*;
+use charsheet::{Character, ItemState};
+
+const DEFINITION: &str = r#"
+main_inventory = "backpack"
+rounds_per_minute = 6
+
+[[values]]
+id = "strength"
+default = 14
+front_end = { name = "Strength", name_short = "STR" }
+
+[[values]]
+id = "strength_mod"
+dependencies = ["strength / 2 - 5"]
+
+[[values]]
+id = "armor"
+default = 10
+
+[[values]]
+id = "burden"
+loads = [{ inventory = "backpack" }]
+
+[[values]]
+id = "initiative"
+
+[[choices]]
+id = "race"
+
+[[choices.selections]]
+front_end = { name = "Dwarf" }
+modifications = [{ value = "strength", formula = "_ + 2" }]
+roll_modifiers = [{ roll = "athletics", bonus = "strength_mod" }]
+
+[[inventories]]
+id = "backpack"
+capacity = "strength * 10"
+slots = "20"
+
+[[inventories]]
+id = "quiver"
+weight_percent = 50
+
+[[items]]
+id = "chestplate"
+physical = { size = 10, stack_size = 1 }
+slots = ["body"]
+durability = 40
+modifications = [
+    { value = "armor", priority = 1, formula = "_ + 4 + strength_mod", stacking = { typed = "armor" } },
+    { value = "initiative", formula = "-1", stacking = "at_most" },
+]
+roll_modifiers = [{ roll = "athletics", disadvantage = true }]
+
+[[items]]
+id = "wand"
+physical = { size = 0, stack_size = 10 }
+charges = 7
+
+[[items]]
+id = "overburdened"
+condition = "burden > strength * 5"
+modifications = [{ value = "initiative", formula = "_ - 2" }]
+
+[[rolls]]
+id = "athletics"
+dice = "1d20 + strength_mod"
+bonus = "2"
+
+[[resources]]
+id = "hp"
+maximum = "strength_mod * 4"
+
+[[recoveries]]
+id = "short_rest"
+refreshes = [{ resource = "hp", amount = "strength_mod" }]
+recharges = [{ item = "wand", amount = "strength_mod - 1" }]
+
+[[slots]]
+id = "body"
+count = 1
+
+[[groups]]
+id = "abilities"
+values = ["strength", "strength_mod"]
+resources = ["hp"]
+"#;
+
+fn check(model: &Model) {
+    let chestplate = model.items().id("chestplate");
+    let burden = model.values().id("burden");
+
+    let abilities = model.groups().get(model.groups().id("abilities"));
+    assert!(abilities.contains_value(model.values().id("strength_mod")));
+    assert!(!abilities.contains_value(burden));
+    assert!(abilities.contains_resource(model.resources().id("hp")));
+
+    assert_eq!(model.items().get(chestplate).durability(), Some(40));
+    assert_eq!(model.rounds_per_minute(), 6);
+
+    let mut character = Character::new(model);
+    assert_eq!(character.get(model.values().id("strength")), 16);
+    assert_eq!(character.get(model.values().id("strength_mod")), 3);
+
+    assert_eq!(character.store(None, chestplate, 20), 4);
+    character.equip(chestplate).unwrap();
+    assert!(character.equip(chestplate).is_err());
+    assert_eq!(character.get(model.values().id("armor")), 17);
+    assert_eq!(character.get(burden), 160);
+    assert_eq!(character.get(model.values().id("initiative")), -3);
+
+    let athletics = model.rolls().id("athletics");
+    let result = character.roll(athletics, &mut SplitMix64::new(0));
+    assert_eq!(result.parts[0].dice.len(), 2);
+    let bonuses: Vec<_> = result.parts[1..].iter().map(|part| part.total).collect();
+    assert_eq!(bonuses, vec![3, 2, 3]);
+
+    let hp = model.resources().id("hp");
+    assert_eq!(character.maximum(hp), 12);
+    character.spend(hp, 10).unwrap();
+    let wand = model.items().id("wand");
+    assert_eq!(character.store(None, wand, 1), 0);
+    let empty = ItemState {
+        charges: Some(0),
+        ..ItemState::default()
+    };
+    character.set_state(None, wand, 0, empty).unwrap();
+    character.apply_event("short_rest").unwrap();
+    assert_eq!(character.current(hp), 5);
+    assert_eq!(character.stacks(None, wand)[0].0.charges, Some(2));
+}
+
+#[test]
+fn toml() {
+    let model: Model = toml::from_str(DEFINITION).unwrap();
+    check(&model);
+
+    let text = toml::to_string(&model).unwrap();
+    let reloaded: Model = toml::from_str(&text).unwrap();
+    check(&reloaded);
+    assert_eq!(toml::to_string(&reloaded).unwrap(), text);
+}
+
+#[test]
+fn json() {
+    let model: Model = toml::from_str(DEFINITION).unwrap();
+
+    let text = serde_json::to_string_pretty(&model).unwrap();
+    assert!(text.contains(r#""formula": "_ + 4 + strength_mod""#));
+    let reloaded: Model = serde_json::from_str(&text).unwrap();
+    check(&reloaded);
+}
+
+#[test]
+fn ron() {
+    let model: Model = toml::from_str(DEFINITION).unwrap();
+
+    let text = ron::to_string(&model).unwrap();
+    let reloaded: Model = ron::from_str(&text).unwrap();
+    check(&reloaded);
+}
+
+fn error(json: &str) -> String {
+    serde_json::from_str::<Model>(json)
+        .err()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn errors() {
+    assert!(error(r#"{ "values": [{ "id": "a" }, { "id": "a" }] }"#)
+        .starts_with("value `a`: duplicate id `a`"));
+    assert!(error(r#"{ "main_inventory": "bag" }"#)
+        .starts_with("main inventory `bag`: unknown id `bag`"));
+    assert!(error(r#"{ "items": [{ "id": "i", "slots": ["head"] }] }"#)
+        .starts_with("item `i`: unknown id `head`"));
+    assert!(
+        error(r#"{ "values": [{ "id": "a", "loads": [{ "inventory": "bag" }] }] }"#)
+            .starts_with("value `a`: unknown id `bag`")
+    );
+    assert!(
+        error(r#"{ "values": [{ "id": "a", "dependencies": ["a + b"] }] }"#)
+            .starts_with("value `a`: unknown value `b` at 4..5")
+    );
+    assert!(
+        error(r#"{ "values": [{ "id": "a", "dependencies": ["a"] }] }"#)
+            .starts_with("value `a`: dependency cycle: a -> a")
+    );
+    assert!(
+        error(r#"{ "items": [{ "id": "i", "physical": { "size": 1, "stack_size": 0 } }] }"#)
+            .starts_with("item `i`: stack size can not be zero")
+    );
+    assert!(error(r#"{ "rolls": [{ "id": "r", "dice": "1d0" }] }"#)
+        .starts_with("roll `r`: invalid dice at 0..3"));
+    assert!(error(r#"{ "groups": [{ "id": "g", "items": ["i"] }] }"#)
+        .starts_with("group `g`: unknown id `i`"));
+    assert!(
+        error(r#"{ "resources": [{ "id": "r", "maximum": "_" }] }"#).starts_with("resource `r`: ")
+    );
+    assert!(
+        error(r#"{ "recoveries": [{ "id": "r", "refreshes": [{ "resource": "x" }] }] }"#)
+            .starts_with("recovery `r`: unknown id `x`")
+    );
+}
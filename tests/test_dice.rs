@@ -0,0 +1,164 @@
+use charsheet::dice::*;
+use charsheet::model::*;
+use charsheet::Character;
+
+fn build_model() -> Model {
+    let mut model = Model::new();
+    model.add_value("strength_mod", Value::new(3));
+    model.add_value("d6", Value::new(1));
+    model
+}
+
+#[test]
+fn parse_and_display() {
+    let model = build_model();
+    let strength_mod = model.values().id("strength_mod");
+
+    let dice = Dice::parse("1d20 + strength_mod + 2", &model).unwrap();
+    let expected = Dice::new(DiceGroup::new(1, 20)).plus(strength_mod).plus(2);
+    assert_eq!(dice, expected);
+
+    for (source, display) in &[
+        ("1d20 + strength_mod + 2", "1d20 + strength_mod + 2"),
+        ("d8+1", "1d8 + 1"),
+        ("4d6k3", "4d6kh3"),
+        ("2d20kl1", "2d20kl1"),
+        ("d20adv - strength_mod", "2d20kh1 - strength_mod"),
+        ("-1 + 2d8dis", "-1 + 4d8kl2"),
+        ("3d6!", "3d6!"),
+        ("`d6` + d6", "`d6` + 1d6"),
+    ] {
+        let dice = Dice::parse(source, &model).unwrap();
+        let text = dice.display(&model).to_string();
+        assert_eq!(&text, display);
+        assert_eq!(Dice::parse(&text, &model).unwrap(), dice);
+    }
+
+    let dice = Dice::new(DiceGroup::new(1, 4)).plus(-2);
+    assert_eq!(dice.display(&model).to_string(), "1d4 - 2");
+}
+
+#[test]
+fn parse_errors() {
+    let model = build_model();
+    let error = |source| Dice::parse(source, &model).unwrap_err();
+
+    assert_eq!(error("1d0").kind(), &DiceErrorKind::InvalidDice);
+    assert_eq!(error("2d6kh3").kind(), &DiceErrorKind::InvalidDice);
+    assert_eq!(error("1d1!").kind(), &DiceErrorKind::InvalidDice);
+    assert_eq!(error("40000d6adv").kind(), &DiceErrorKind::InvalidDice);
+    assert_eq!(error("40000d6dis").kind(), &DiceErrorKind::InvalidDice);
+    assert_eq!(error("1d20 + 0d6").span(), 7..10);
+    assert_eq!(
+        error("1d20 + wisdom").kind(),
+        &DiceErrorKind::UnknownIdentifier("wisdom".into())
+    );
+    assert_eq!(error("1d20 + wisdom").span(), 7..13);
+    assert_eq!(error("1d20 +").kind(), &DiceErrorKind::UnexpectedEnd);
+    assert_eq!(
+        error("1d20 * 2").kind(),
+        &DiceErrorKind::UnexpectedCharacter('*')
+    );
+}
+
+#[test]
+fn reproducible_rolls() {
+    let model = build_model();
+    let character = Character::new(&model);
+    let dice = Dice::parse("1d20 + strength_mod + 2", &model).unwrap();
+
+    let first = character.roll_dice(&dice, &mut SplitMix64::new(42));
+    let second = character.roll_dice(&dice, &mut SplitMix64::new(42));
+    assert_eq!(first, second);
+
+    assert_eq!(first.parts.len(), 3);
+    let die = &first.parts[0].dice[0];
+    assert!((1..=20).contains(&die.total()));
+    assert_eq!(first.parts[1].total, 3);
+    assert_eq!(first.parts[2].total, 2);
+    assert_eq!(first.total, die.total() as i32 + 5);
+}
+
+#[test]
+fn uniform_faces() {
+    let mut rng = SplitMix64::new(7);
+    let mut seen = [0; 6];
+    for _ in 0..6000 {
+        seen[usize::from(rng.die(6)) - 1] += 1;
+    }
+    assert!(seen.iter().all(|&n| n > 900 && n < 1100));
+}
+
+#[test]
+fn keep_dice() {
+    let model = build_model();
+    let character = Character::new(&model);
+    let dice = Dice::parse("4d6kh3 - 2d10kl1", &model).unwrap();
+    let mut rng = SplitMix64::new(1);
+
+    for _ in 0..100 {
+        let result = character.roll_dice(&dice, &mut rng);
+        let highest = &result.parts[0].dice;
+        assert_eq!(highest.iter().filter(|die| die.kept).count(), 3);
+        let dropped = highest.iter().find(|die| !die.kept).unwrap();
+        assert!(highest.iter().all(|die| die.total() >= dropped.total()));
+
+        let lowest = &result.parts[1].dice;
+        let kept = lowest.iter().find(|die| die.kept).unwrap();
+        assert!(lowest.iter().all(|die| die.total() >= kept.total()));
+        assert_eq!(result.parts[1].total, -(kept.total() as i32));
+    }
+}
+
+#[test]
+fn exploding_dice() {
+    let model = build_model();
+    let character = Character::new(&model);
+    let dice = Dice::new(DiceGroup::new(10, 2).explode());
+    let mut rng = SplitMix64::new(3);
+
+    let result = character.roll_dice(&dice, &mut rng);
+    let rolls = &result.parts[0].dice;
+    assert!(rolls.iter().any(|die| die.faces.len() > 1));
+    for die in rolls {
+        let (last, rest) = die.faces.split_last().unwrap();
+        assert_eq!(*last, 1);
+        assert!(rest.iter().all(|&face| face == 2));
+    }
+    let sum: u32 = rolls.iter().map(DieRoll::total).sum();
+    assert_eq!(result.total, sum as i32);
+}
+
+#[test]
+fn clamped_totals() {
+    let model = build_model();
+    let character = Character::new(&model);
+    let mut rng = SplitMix64::new(5);
+
+    let mut roll = |source| {
+        let dice = Dice::parse(source, &model).unwrap();
+        let result = character.roll_dice(&dice, &mut rng);
+        let sum: i64 = result
+            .parts
+            .iter()
+            .map(|part| {
+                let sum: i64 = part.dice.iter().map(|die| i64::from(die.total())).sum();
+                if part.negative {
+                    -sum
+                } else {
+                    sum
+                }
+            })
+            .sum();
+        (result.total, sum)
+    };
+
+    let (total, sum) = roll("65535d65535!");
+    assert_eq!(i64::from(total), sum.min(i32::MAX.into()));
+    let (total, sum) = roll("65535d65535 + 65535d65535");
+    assert!(sum > i32::MAX.into());
+    assert_eq!(total, i32::MAX);
+    let (total, sum) = roll("-65535d65535 - 65535d65535");
+    assert!(sum < i32::MIN.into());
+    assert_eq!(total, i32::MIN);
+}
@@ -0,0 +1,82 @@
+use charsheet::model::*;
+
+fn model() -> Model {
+    let mut model = Model::new();
+    model.add_value("strength", Value::new(15));
+    model.add_value("level", Value::new(3));
+    model.add_value("hit dice", Value::new(1));
+    model
+}
+
+fn round_trip(formula: &str) -> String {
+    let model = model();
+    let calc = Calculation::parse(formula, &model).unwrap();
+    let text = calc.display(&model).to_string();
+
+    let reparsed = Calculation::parse(&text, &model).unwrap();
+    assert_eq!(reparsed.display(&model).to_string(), text);
+    text
+}
+
+#[test]
+fn builder() {
+    let model = model();
+    let strength = model.values().id("strength");
+    let level = model.values().id("level");
+
+    let calc = (strength / 2) - 5;
+    assert_eq!(calc.display(&model).to_string(), "strength / 2 - 5");
+
+    let calc = Calculation::max(0.into(), Calculation::from(strength) - (level + 2)) * 3;
+    assert_eq!(
+        calc.display(&model).to_string(),
+        "max(0, strength - (level + 2)) * 3"
+    );
+
+    let calc = Calculation::from(level).mul_f(Rounding::Ceil, 1.5);
+    assert_eq!(calc.display(&model).to_string(), "ceil(level * 1.5)");
+
+    let calc = Calculation::placeholder() + 2;
+    assert_eq!(calc.display(&model).to_string(), "_ + 2");
+
+    let calc = -(Calculation::from(2) * level);
+    assert_eq!(format!("{:?}", calc), "-(2 * #1)");
+}
+
+#[test]
+fn minimal_parentheses() {
+    assert_eq!(
+        round_trip("((strength)) + (level * 2)"),
+        "strength + level * 2"
+    );
+    assert_eq!(
+        round_trip("(strength + level) * 2"),
+        "(strength + level) * 2"
+    );
+    assert_eq!(
+        round_trip("strength - (level - 1)"),
+        "strength - (level - 1)"
+    );
+    assert_eq!(round_trip("(strength - level) - 1"), "strength - level - 1");
+    assert_eq!(round_trip("(1 < 2) == (2 > 1)"), "1 < 2 == 2 > 1");
+    assert_eq!(
+        round_trip("(1 || 0) && !(level > 2)"),
+        "(1 || 0) && !(level > 2)"
+    );
+}
+
+#[test]
+fn round_trips() {
+    assert_eq!(
+        round_trip("ceil(strength / (level + 1))"),
+        "ceil(strength / (level + 1))"
+    );
+    assert_eq!(round_trip("round(-level * 2.0)"), "round(-level * 2.0)");
+    assert_eq!(round_trip("0.25 * strength"), "strength * 0.25");
+    assert_eq!(round_trip("-(5) + -5 - --5"), "-(5) + -5 - --5");
+    assert_eq!(
+        round_trip("abs(min(strength, level % 2))"),
+        "abs(min(strength, level % 2))"
+    );
+    assert_eq!(round_trip("`hit dice` * _"), "`hit dice` * _");
+}
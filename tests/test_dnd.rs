@@ -1,5 +1,5 @@
 use charsheet::model::{Calculation, Choice, Model, Modification, Selection, Value};
-use charsheet::Character;
+use charsheet::{Character, CharacterError};
 
 fn dnd_model() -> Model {
     let mut model = Model::new();
@@ -37,6 +37,18 @@ fn dnd_model() -> Model {
         ),
     );
 
+    // Elf
+    model.add_selection(
+        race,
+        Selection::new(
+            vec![(
+                model.values().id("dexterity"),
+                Modification::new(0, Calculation::placeholder() + 2),
+            )]
+            .into_iter(),
+        ),
+    );
+
     // TODO extend
 
     model
@@ -60,3 +72,49 @@ fn test_ability_modifiers() {
     assert_eq!(character.get(model.values().id("constitution_mod")), 1);
     assert_eq!(character.get(model.values().id("intelligence_mod")), 0);
 }
+
+#[test]
+fn test_race_selection() {
+    let model = dnd_model();
+    let race = model.choices().id("race");
+
+    let mut character = Character::new(&model);
+    assert_eq!(character.selection(race), 0);
+
+    // Character is elf
+    character.select(race, 1).unwrap();
+    assert_eq!(character.selection(race), 1);
+    assert_eq!(character.get(model.values().id("constitution")), 10);
+    assert_eq!(character.get(model.values().id("dexterity")), 12);
+    assert_eq!(character.get(model.values().id("dexterity_mod")), 1);
+
+    assert_eq!(
+        character.select(race, 2),
+        Err(CharacterError::InvalidSelection {
+            choice: race,
+            index: 2
+        })
+    );
+    assert_eq!(character.selection(race), 1);
+}
+
+#[test]
+fn test_unreachable_selection() {
+    let mut model = Model::new();
+    let choice = model.add_choice("choice", Choice::new());
+    for _ in 0..=usize::from(u16::MAX) + 1 {
+        model.add_selection(choice, Selection::new(std::iter::empty()));
+    }
+
+    let mut character = Character::new(&model);
+    let index = usize::from(u16::MAX) + 1;
+    assert_eq!(
+        character.select(choice, index),
+        Err(CharacterError::InvalidSelection { choice, index })
+    );
+    assert_eq!(character.selection(choice), 0);
+
+    character.select(choice, index - 1).unwrap();
+    assert_eq!(character.selection(choice), index - 1);
+    assert!(Character::load(&model, "select 65536 choice".as_bytes()).is_err());
+}
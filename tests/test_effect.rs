@@ -0,0 +1,165 @@
+use charsheet::model::*;
+use charsheet::{Character, CharacterError, Duration};
+
+#[test]
+fn expire_after_rounds() {
+    let mut model = Model::new();
+    let attack = model.add_value("attack", Value::new(3));
+    let bless = model.add_item("bless", Item::new());
+    model.add_modification(
+        bless,
+        attack,
+        Modification::new(0, Calculation::placeholder() + 2),
+    );
+    let poisoned = model.add_item("poisoned", Item::new());
+    model.add_modification(
+        poisoned,
+        attack,
+        Modification::new(0, Calculation::placeholder() - 1),
+    );
+
+    let mut character = Character::new(&model);
+    character.apply_effect(bless, Duration::Rounds(10)).unwrap();
+    character
+        .apply_effect(poisoned, Duration::Rounds(1))
+        .unwrap();
+    assert_eq!(character.get(attack), 4);
+
+    character.advance_time(1);
+    assert_eq!(character.get(attack), 5);
+    assert_eq!(character.effects(), &[(bless, Duration::Rounds(9))]);
+
+    // Stacks with itself and with equipping the item
+    character.apply_effect(bless, Duration::Rounds(3)).unwrap();
+    character.equip(bless).unwrap();
+    assert_eq!(character.get(attack), 9);
+
+    character.advance_time(5);
+    assert_eq!(character.get(attack), 7);
+    character.advance_time(4);
+    assert_eq!(character.get(attack), 5);
+    assert!(character.effects().is_empty());
+}
+
+#[test]
+fn dependent_values() {
+    let mut model = Model::new();
+    let speed = model.add_value("speed", Value::new(30));
+    let fast = model.add_value("fast", Value::new(0));
+    model.add_dependency(fast, speed / 40);
+    let haste = model.add_item("haste", Item::new());
+    model.add_modification(
+        haste,
+        speed,
+        Modification::new(0, Calculation::placeholder() * 2),
+    );
+
+    let mut character = Character::new(&model);
+    character.apply_effect(haste, Duration::Minutes(1)).unwrap();
+    assert_eq!(character.get(fast), 1);
+    assert_eq!(character.effects(), &[(haste, Duration::Rounds(10))]);
+
+    let events = character.subscribe(None);
+    character.advance_time(10);
+    assert_eq!(character.get(fast), 0);
+    assert_eq!(character.poll(events).len(), 3);
+}
+
+#[test]
+fn rounds_per_minute() {
+    let mut model = Model::new();
+    let bless = model.add_item("bless", Item::new());
+    assert_eq!(model.rounds_per_minute(), 10);
+    model.set_rounds_per_minute(6);
+
+    let mut character = Character::new(&model);
+    character.apply_effect(bless, Duration::Minutes(2)).unwrap();
+    assert_eq!(character.effects(), &[(bless, Duration::Rounds(12))]);
+}
+
+#[test]
+fn until_event() {
+    let mut model = Model::new();
+    let attack = model.add_value("attack", Value::new(3));
+    let bless = model.add_item("bless", Item::new());
+    model.add_modification(
+        bless,
+        attack,
+        Modification::new(0, Calculation::placeholder() + 2),
+    );
+    let long_rest = model.add_recovery("long_rest", Recovery::new());
+
+    let mut character = Character::new(&model);
+    character
+        .apply_effect(bless, Duration::UntilEvent(long_rest))
+        .unwrap();
+    character.advance_time(1000);
+    assert_eq!(character.get(attack), 5);
+
+    character.apply_event("long_rest").unwrap();
+    assert_eq!(character.get(attack), 3);
+
+    character.undo();
+    assert_eq!(character.get(attack), 5);
+    assert_eq!(
+        character.effects(),
+        &[(bless, Duration::UntilEvent(long_rest))]
+    );
+}
+
+#[test]
+fn separate_from_equipping() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let attack = model.add_value("attack", Value::new(3));
+    let hand = model.add_slot("hand", Slot::new(1));
+    let sword = model.add_item("sword", Item::new().set_physical(3, 1).occupy(hand));
+    model.add_modification(
+        sword,
+        attack,
+        Modification::new(0, Calculation::placeholder() + 2),
+    );
+
+    let mut character = Character::new(&model);
+    character.apply_effect(sword, Duration::Rounds(2)).unwrap();
+    assert_eq!(character.equipped(sword), 0);
+    assert_eq!(character.applied(sword), 1);
+    assert_eq!(character.get(attack), 5);
+    assert_eq!(
+        character.unequip(sword),
+        Err(CharacterError::NotEquipped(sword))
+    );
+
+    // Unequipping items that are no longer carried keeps their effects
+    character.store(None, sword, 1);
+    character.equip(sword).unwrap();
+    assert_eq!(character.get(attack), 7);
+    assert_eq!(character.take(None, sword, 1), 0);
+    assert_eq!(character.applied(sword), 1);
+    assert_eq!(character.get(attack), 5);
+
+    character.advance_time(2);
+    assert_eq!(character.get(attack), 3);
+    character.undo();
+    assert_eq!(character.applied(sword), 1);
+    assert_eq!(character.get(attack), 5);
+}
+
+#[test]
+fn invalid_effects() {
+    let mut model = Model::new();
+    let speed = model.add_value("speed", Value::new(30));
+    let prone = model.add_item("prone", Item::new().set_condition(speed / 100));
+    let bless = model.add_item("bless", Item::new());
+
+    let mut character = Character::new(&model);
+    assert_eq!(
+        character.apply_effect(prone, Duration::Rounds(1)),
+        Err(CharacterError::Conditional(prone))
+    );
+
+    character.apply_effect(bless, Duration::Rounds(0)).unwrap();
+    assert_eq!(character.equipped(bless), 0);
+    assert!(character.effects().is_empty());
+}
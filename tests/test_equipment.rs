@@ -0,0 +1,197 @@
+use charsheet::model::*;
+use charsheet::{Character, CharacterError};
+
+fn build_model() -> Model {
+    let mut model = Model::new();
+
+    let armor = model.add_value("armor", Value::new(10));
+
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let pack = model.add_inventory("pack", Inventory::new());
+
+    let body = model.add_slot("body", Slot::new(1));
+    let ring = model.add_slot("ring", Slot::new(2));
+    let main_hand = model.add_slot("main_hand", Slot::new(1));
+    let off_hand = model.add_slot("off_hand", Slot::new(1));
+
+    let chestplate = model.add_item("chestplate", Item::new().set_physical(10, 1).occupy(body));
+    model.add_modification(
+        chestplate,
+        armor,
+        Modification::new(0, Calculation::placeholder() + 4),
+    );
+    model.add_item("robe", Item::new().set_physical(2, 1).occupy(body));
+    model.add_item("ring", Item::new().set_physical(0, 10).occupy(ring));
+    model.add_item(
+        "greatsword",
+        Item::new()
+            .set_physical(6, 1)
+            .occupy(main_hand)
+            .occupy(off_hand),
+    );
+    model.add_item("dagger", Item::new().set_physical(1, 5).occupy(main_hand));
+    model.add_item(
+        "backpack",
+        Item::new().set_physical(2, 1).set_inventory(pack),
+    );
+    model.add_item("torch", Item::new().set_physical(1, 5));
+    model.add_item("blessing", Item::new());
+
+    model
+}
+
+#[test]
+fn requires_carried_item() {
+    let model = build_model();
+    let armor = model.values().id("armor");
+    let chestplate = model.items().id("chestplate");
+    let torch = model.items().id("torch");
+    let blessing = model.items().id("blessing");
+
+    let mut character = Character::new(&model);
+    assert_eq!(
+        character.equip(chestplate),
+        Err(CharacterError::NotCarried(chestplate))
+    );
+    assert_eq!(character.get(armor), 10);
+
+    character.store(None, chestplate, 1);
+    character.equip(chestplate).unwrap();
+    assert_eq!(character.get(armor), 14);
+
+    // Physical items without slots need to be carried as well
+    character.store(None, torch, 1);
+    character.equip(torch).unwrap();
+    assert_eq!(
+        character.equip(torch),
+        Err(CharacterError::NotCarried(torch))
+    );
+    assert_eq!(character.take(None, torch, 1), 0);
+    assert_eq!(character.equipped(torch), 0);
+
+    // Items that are not physical are not bound to the inventory
+    character.equip(blessing).unwrap();
+    character.equip(blessing).unwrap();
+    assert_eq!(character.equipped(blessing), 2);
+}
+
+#[test]
+fn slot_conflicts() {
+    let model = build_model();
+    let body = model.slots().id("body");
+    let main_hand = model.slots().id("main_hand");
+    let chestplate = model.items().id("chestplate");
+    let robe = model.items().id("robe");
+    let ring = model.items().id("ring");
+    let greatsword = model.items().id("greatsword");
+    let dagger = model.items().id("dagger");
+
+    let mut character = Character::new(&model);
+    character.store(None, chestplate, 1);
+    character.store(None, robe, 1);
+    character.store(None, ring, 3);
+    character.store(None, greatsword, 1);
+    character.store(None, dagger, 2);
+
+    character.equip(chestplate).unwrap();
+    assert_eq!(
+        character.equip(robe),
+        Err(CharacterError::SlotOccupied {
+            item: robe,
+            slot: body
+        })
+    );
+    assert_eq!(character.occupants(body), vec![chestplate]);
+
+    // Slots can hold more than one item
+    character.equip(ring).unwrap();
+    character.equip(ring).unwrap();
+    assert!(character.equip(ring).is_err());
+    assert_eq!(character.equipped(ring), 2);
+
+    // Items can need more than one slot
+    character.equip(dagger).unwrap();
+    assert_eq!(
+        character.equip(greatsword),
+        Err(CharacterError::SlotOccupied {
+            item: greatsword,
+            slot: main_hand
+        })
+    );
+    character.unequip(dagger).unwrap();
+    character.equip(greatsword).unwrap();
+    assert!(character.equip(dagger).is_err());
+}
+
+#[test]
+fn removing_unequips() {
+    let model = build_model();
+    let armor = model.values().id("armor");
+    let chestplate = model.items().id("chestplate");
+    let ring = model.items().id("ring");
+    let backpack = model.items().id("backpack");
+
+    let mut character = Character::new(&model);
+    character.store(None, ring, 2);
+    character.equip(ring).unwrap();
+    character.equip(ring).unwrap();
+    assert_eq!(character.take(None, ring, 1), 0);
+    assert_eq!(character.equipped(ring), 1);
+
+    // Moving between carried inventories keeps items equipped
+    character.store(None, backpack, 1);
+    let pack = character.containers(None, backpack)[0];
+    character.store(Some(pack), chestplate, 1);
+    character.equip(chestplate).unwrap();
+    assert_eq!(character.transfer(Some(pack), None, chestplate, 1), 1);
+    assert_eq!(character.transfer(None, Some(pack), chestplate, 1), 1);
+    assert_eq!(character.equipped(chestplate), 1);
+    assert_eq!(character.carried(chestplate), 1);
+
+    // Dropping a container unequips its content
+    character.drop(None, backpack);
+    assert_eq!(character.equipped(chestplate), 0);
+    assert_eq!(character.get(armor), 10);
+
+    character.undo();
+    assert_eq!(character.equipped(chestplate), 1);
+    assert_eq!(character.get(armor), 14);
+}
+
+#[test]
+fn moving_into_dropped_containers() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let pack = model.add_inventory("pack", Inventory::new());
+    let body = model.add_slot("body", Slot::new(1));
+    let finger = model.add_slot("finger", Slot::new(1));
+    let chestplate = model.add_item("chestplate", Item::new().set_physical(10, 1).occupy(body));
+    let ring = model.add_item("ring", Item::new().set_physical(0, 10).occupy(finger));
+    let backpack = model.add_item(
+        "backpack",
+        Item::new().set_physical(2, 1).set_inventory(pack),
+    );
+
+    let mut character = Character::new(&model);
+    character.store(None, backpack, 2);
+    let packs = character.containers(None, backpack);
+    assert_eq!(character.take(None, backpack, 1), 0);
+    let kept = character.containers(None, backpack)[0];
+    let dropped = packs.into_iter().find(|&pack| pack != kept).unwrap();
+
+    character.store(None, chestplate, 1);
+    character.equip(chestplate).unwrap();
+    assert_eq!(character.transfer(None, Some(dropped), chestplate, 1), 1);
+    assert_eq!(character.equipped(chestplate), 0);
+    character.undo();
+    assert_eq!(character.equipped(chestplate), 1);
+
+    // Equipped content goes along with its container
+    character.store(Some(kept), ring, 1);
+    character.equip(ring).unwrap();
+    assert_eq!(character.transfer(None, Some(dropped), backpack, 1), 1);
+    assert_eq!(character.equipped(ring), 0);
+    assert_eq!(character.equipped(chestplate), 1);
+}
@@ -0,0 +1,168 @@
+use charsheet::model::*;
+use charsheet::{Character, Event};
+
+struct Ids {
+    strength: Id<Value>,
+    modifier: Id<Value>,
+    burden: Id<Value>,
+    torch: Id<Item>,
+    overburdened: Id<Item>,
+    abilities: Id<Group>,
+}
+
+fn build_model() -> (Model, Ids) {
+    let mut model = Model::new();
+
+    let strength = model.add_value("strength", Value::new(10));
+    let modifier = model.add_value("modifier", Value::new(-5));
+    model.add_dependency(modifier, strength / 2);
+    let burden = model.add_value("burden", Value::new(0));
+
+    let inventory = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(inventory);
+
+    let torch = model.add_item("torch", Item::new().set_physical(1, 5));
+    let overburdened = model.add_item(
+        "overburdened",
+        Item::new().set_condition(Calculation::gt(burden.into(), 10)),
+    );
+    model.add_modification(
+        overburdened,
+        strength,
+        Modification::new(0, Calculation::placeholder() - 2),
+    );
+
+    let abilities = model.add_group("abilities", Group::new().values(vec![strength, modifier]));
+
+    let ids = Ids {
+        strength,
+        modifier,
+        burden,
+        torch,
+        overburdened,
+        abilities,
+    };
+    (model, ids)
+}
+
+#[test]
+fn value_events() {
+    let (model, ids) = build_model();
+    let mut character = Character::new(&model);
+    let all = character.subscribe(None);
+
+    character.set_base(ids.strength, 14);
+    assert_eq!(
+        character.poll(all),
+        vec![
+            Event::Value {
+                id: ids.strength,
+                old: 10,
+                new: 14
+            },
+            Event::Value {
+                id: ids.modifier,
+                old: 0,
+                new: 2
+            },
+        ]
+    );
+    assert!(character.poll(all).is_empty());
+
+    // Odd strength does not change the modifier
+    character.set_base(ids.strength, 15);
+    assert_eq!(
+        character.poll(all),
+        vec![Event::Value {
+            id: ids.strength,
+            old: 14,
+            new: 15
+        }]
+    );
+}
+
+#[test]
+fn condition_events() {
+    let (model, ids) = build_model();
+    let mut character = Character::new(&model);
+    let all = character.subscribe(None);
+
+    character.set_base(ids.burden, 11);
+    assert_eq!(
+        character.poll(all),
+        vec![
+            Event::Value {
+                id: ids.strength,
+                old: 10,
+                new: 8
+            },
+            Event::Value {
+                id: ids.modifier,
+                old: 0,
+                new: -1
+            },
+            Event::Value {
+                id: ids.burden,
+                old: 0,
+                new: 11
+            },
+            Event::Item {
+                id: ids.overburdened,
+                old: 0,
+                new: 1
+            },
+        ]
+    );
+}
+
+#[test]
+fn inventory_events() {
+    let (model, ids) = build_model();
+    let mut character = Character::new(&model);
+    let all = character.subscribe(None);
+    let inventory = character.main_inventory().unwrap();
+
+    character.store(None, ids.torch, 3);
+    character.take(None, ids.torch, 1);
+    character.drop(None, ids.torch);
+    let events = character.poll(all);
+    let amounts: Vec<_> = events
+        .iter()
+        .map(|event| match event {
+            Event::Inventory {
+                inventory: i,
+                item,
+                old,
+                new,
+            } => {
+                assert_eq!(*i, inventory);
+                assert_eq!(*item, ids.torch);
+                (*old, *new)
+            }
+            event => panic!("unexpected event {:?}", event),
+        })
+        .collect();
+    assert_eq!(amounts, vec![(0, 3), (3, 2), (2, 0)]);
+}
+
+#[test]
+fn group_subscription() {
+    let (model, ids) = build_model();
+    let mut character = Character::new(&model);
+    let abilities = character.subscribe(Some(ids.abilities));
+    let all = character.subscribe(None);
+
+    character.set_base(ids.burden, 11);
+    let events = character.poll(abilities);
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|event| match event {
+        Event::Value { id, .. } => *id == ids.strength || *id == ids.modifier,
+        _ => false,
+    }));
+    assert_eq!(character.poll(all).len(), 4);
+
+    character.unsubscribe(abilities);
+    character.set_base(ids.strength, 20);
+    assert!(character.poll(abilities).is_empty());
+    assert_eq!(character.poll(all).len(), 2);
+}
@@ -0,0 +1,73 @@
+use charsheet::model::*;
+use charsheet::{Character, ModificationSource};
+
+#[test]
+fn explain_armor() {
+    let mut model = Model::new();
+    let dexterity = model.add_value("dexterity", Value::new(14));
+    let armor = model.add_value("armor", Value::new(10));
+    model.add_dependency(armor, dexterity / 2 - 5);
+
+    let chestplate = model.add_item("chestplate", Item::new());
+    model.add_modification(
+        chestplate,
+        armor,
+        Modification::new(1, Calculation::placeholder() + 4),
+    );
+    let ring = model.add_item("ring", Item::new());
+    model.add_modification(
+        ring,
+        armor,
+        Modification::new(0, Calculation::placeholder() + 1),
+    );
+
+    let class = model.add_choice("class", Choice::new());
+    model.add_selection(class, Selection::new(std::iter::empty()));
+    let modification = Modification::new(2, Calculation::placeholder().max(18));
+    model.add_selection(
+        class,
+        Selection::new(vec![(armor, modification)].into_iter()),
+    );
+
+    let mut character = Character::new(&model);
+    character.equip(chestplate).unwrap();
+    character.equip(ring).unwrap();
+    character.equip(ring).unwrap();
+
+    let explanation = character.explain(armor);
+    assert_eq!(explanation.base, 10);
+    assert_eq!(explanation.dependencies.len(), 1);
+    assert_eq!(
+        explanation.dependencies[0]
+            .calculation
+            .display(&model)
+            .to_string(),
+        "dexterity / 2 - 5"
+    );
+    assert_eq!(explanation.dependencies[0].contribution, 2);
+
+    let steps: Vec<_> = explanation
+        .modifications
+        .iter()
+        .map(|step| (step.source, step.count, step.before, step.after))
+        .collect();
+    assert_eq!(
+        steps,
+        vec![
+            (ModificationSource::Item(ring), 2, 12, 14),
+            (ModificationSource::Item(chestplate), 1, 14, 18),
+        ]
+    );
+    assert_eq!(explanation.actual, 18);
+    assert_eq!(explanation.actual, character.get(armor));
+
+    character.select(class, 1).unwrap();
+    character.set_base(armor, 11);
+    let explanation = character.explain(armor);
+    assert_eq!(explanation.modifications.len(), 3);
+    let last = &explanation.modifications[2];
+    assert_eq!(last.source, ModificationSource::Choice(class));
+    assert_eq!((last.before, last.after), (19, 19));
+    assert_eq!(last.modification.priority(), 2);
+    assert_eq!(explanation.actual, character.get(armor));
+}
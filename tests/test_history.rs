@@ -0,0 +1,126 @@
+use charsheet::model::*;
+use charsheet::Character;
+
+fn build_model() -> Model {
+    let mut model = Model::new();
+
+    let strength = model.add_value("strength", Value::new(10));
+    let armor = model.add_value("armor", Value::new(10));
+    model.add_dependency(armor, strength / 2 - 5);
+
+    let race = model.add_choice("race", Choice::new());
+    model.add_selection(race, Selection::new(std::iter::empty()));
+    let modification = Modification::new(0, Calculation::placeholder() + 2);
+    model.add_selection(
+        race,
+        Selection::new(vec![(strength, modification)].into_iter()),
+    );
+
+    let inventory = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(inventory);
+    let shield = model.add_item("shield", Item::new().set_physical(5, 1));
+    model.add_modification(
+        shield,
+        armor,
+        Modification::new(0, Calculation::placeholder() + 2),
+    );
+
+    model
+}
+
+#[test]
+fn undo_redo() {
+    let model = build_model();
+    let strength = model.values().id("strength");
+    let armor = model.values().id("armor");
+    let race = model.choices().id("race");
+    let shield = model.items().id("shield");
+
+    let mut character = Character::new(&model);
+    assert!(!character.can_undo());
+
+    character.set_base(strength, 14);
+    character.select(race, 1).unwrap();
+    character.store(None, shield, 2);
+    character.equip(shield).unwrap();
+    character.take(None, shield, 1);
+    assert_eq!(character.get(armor), 15);
+
+    assert!(character.undo());
+    assert_eq!(character.amount(None, shield), 2);
+    assert!(character.undo());
+    assert_eq!(character.get(armor), 13);
+    assert!(character.undo());
+    assert_eq!(character.amount(None, shield), 0);
+    assert_eq!(character.fill(None), 0);
+    assert!(character.undo());
+    assert_eq!(character.selection(race), 0);
+    assert_eq!(character.get(strength), 14);
+    assert!(character.undo());
+    assert_eq!(character.get(armor), 10);
+    assert!(!character.undo());
+
+    assert!(character.redo());
+    assert!(character.redo());
+    assert_eq!(character.get(strength), 16);
+    assert_eq!(character.get(armor), 13);
+
+    // New edits discard undone ones
+    character.set_base(strength, 8);
+    assert!(!character.can_redo());
+    assert!(character.undo());
+    assert_eq!(character.get(strength), 16);
+}
+
+#[test]
+fn batches_and_failures() {
+    let model = build_model();
+    let strength = model.values().id("strength");
+    let shield = model.items().id("shield");
+
+    let mut character = Character::new(&model);
+    character
+        .batch(|tx| {
+            tx.set_base(strength, 12);
+            tx.store(None, shield, 2)?;
+            tx.equip(shield)?;
+            tx.equip(shield)?;
+            Ok(())
+        })
+        .unwrap();
+
+    // Failed edits are not recorded
+    assert!(character.unequip(model.items().id("shield")).is_ok());
+    assert!(character.select(model.choices().id("race"), 3).is_err());
+    assert!(character.batch(|tx| tx.take(None, shield, 3)).is_err());
+
+    assert!(character.undo());
+    assert_eq!(character.equipped(shield), 2);
+    assert!(character.undo());
+    assert_eq!(character.equipped(shield), 0);
+    assert_eq!(character.amount(None, shield), 0);
+    assert_eq!(character.get(strength), 10);
+    assert!(!character.can_undo());
+}
+
+#[test]
+fn history_limit() {
+    let model = build_model();
+    let strength = model.values().id("strength");
+
+    let mut character = Character::new(&model);
+    character.set_history_limit(2);
+    for base in 11..15 {
+        character.set_base(strength, base);
+    }
+
+    assert!(character.undo());
+    assert!(character.undo());
+    assert!(!character.undo());
+    assert_eq!(character.get(strength), 12);
+
+    character.set_history_limit(0);
+    character.set_base(strength, 20);
+    assert!(!character.can_undo());
+    assert!(!character.can_redo());
+}
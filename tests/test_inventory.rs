@@ -1,5 +1,5 @@
 use charsheet::model::*;
-use charsheet::Character;
+use charsheet::{Character, CharacterError};
 
 #[test]
 fn simple_item() {
@@ -77,3 +77,111 @@ fn stacking() {
     assert_eq!(character.store(None, paper_sheet, 10), 0);
     assert_eq!(character.store(None, paper_sheet, 10), 5);
 }
+
+#[test]
+fn take() {
+    let mut model = Model::new();
+
+    let inventory = model.add_inventory("main", Inventory::new().capacity(15));
+    model.set_main_inventory(inventory);
+
+    let chestplate = model.add_item("chestplate", Item::new().set_physical(10, 1));
+    let paper_sheet = model.add_item("paper_sheet", Item::new().set_physical(1, 10));
+
+    let mut character = Character::new(&model);
+
+    assert_eq!(character.store(None, chestplate, 1), 0);
+    assert_eq!(character.store(None, paper_sheet, 5), 0);
+    assert_eq!(character.fill(None), 15);
+
+    assert_eq!(character.take(None, paper_sheet, 2), 0);
+    assert_eq!(character.amount(None, paper_sheet), 3);
+    assert_eq!(character.fill(None), 13);
+
+    assert_eq!(character.take(None, chestplate, 2), 1);
+    assert_eq!(character.amount(None, chestplate), 0);
+    assert_eq!(character.fill(None), 3);
+
+    // Freed space can be used again
+    assert_eq!(character.store(None, chestplate, 1), 0);
+}
+
+#[test]
+fn take_frees_slots() {
+    let mut model = Model::new();
+
+    let inventory = model.add_inventory("main", Inventory::new().slots(2));
+    model.set_main_inventory(inventory);
+
+    let chestplate = model.add_item("chestplate", Item::new().set_physical(10, 1));
+    let paper_sheet = model.add_item("paper_sheet", Item::new().set_physical(1, 10));
+
+    let mut character = Character::new(&model);
+
+    assert_eq!(character.store(None, paper_sheet, 15), 0);
+    assert_eq!(character.store(None, chestplate, 1), 1);
+    assert_eq!(character.take(None, paper_sheet, 5), 0);
+    assert_eq!(character.store(None, chestplate, 1), 0);
+}
+
+#[test]
+fn drop() {
+    let mut model = Model::new();
+
+    let inventory = model.add_inventory("main", Inventory::new().slots(3));
+    model.set_main_inventory(inventory);
+
+    let chestplate = model.add_item("chestplate", Item::new().set_physical(10, 1));
+    let paper_sheet = model.add_item("paper_sheet", Item::new().set_physical(1, 10));
+
+    let mut character = Character::new(&model);
+
+    assert_eq!(character.store(None, paper_sheet, 15), 0);
+    assert_eq!(character.store(None, chestplate, 1), 0);
+    assert_eq!(character.drop(None, paper_sheet), 15);
+    assert_eq!(character.drop(None, paper_sheet), 0);
+    assert_eq!(character.fill(None), 10);
+    assert_eq!(character.store(None, paper_sheet, 20), 0);
+}
+
+#[test]
+fn not_physical() {
+    let mut model = Model::new();
+
+    let inventory = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(inventory);
+
+    let blessing = model.add_item("blessing", Item::new());
+
+    let mut character = Character::new(&model);
+
+    assert_eq!(character.store(None, blessing, 2), 2);
+    assert_eq!(character.take(None, blessing, 1), 1);
+    assert_eq!(character.amount(None, blessing), 0);
+    assert_eq!(
+        character.batch(|transaction| transaction.store(None, blessing, 1)),
+        Err(CharacterError::DoesNotFit {
+            item: blessing,
+            rest: 1
+        })
+    );
+}
+
+#[test]
+fn no_main_inventory() {
+    let mut model = Model::new();
+
+    let chestplate = model.add_item("chestplate", Item::new().set_physical(10, 1));
+
+    let mut character = Character::new(&model);
+
+    assert_eq!(character.main_inventory(), None);
+    assert_eq!(character.store(None, chestplate, 1), 1);
+    assert_eq!(character.take(None, chestplate, 1), 1);
+    assert_eq!(character.drop(None, chestplate), 0);
+    assert_eq!(character.transfer(None, None, chestplate, 1), 0);
+    assert_eq!(character.amount(None, chestplate), 0);
+    assert_eq!(character.fill(None), 0);
+    assert!(character.containers(None, chestplate).is_empty());
+    assert!(character.stacks(None, chestplate).is_empty());
+}
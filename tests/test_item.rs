@@ -1,5 +1,5 @@
 use charsheet::model::{Calculation, Item, Model, Modification, Value};
-use charsheet::Character;
+use charsheet::{Character, CharacterError};
 
 #[test]
 fn simple_modification() {
@@ -16,7 +16,7 @@ fn simple_modification() {
     let mut character = Character::new(&model);
 
     assert_eq!(character.get(armor), 0);
-    character.equip(chestplate);
+    character.equip(chestplate).unwrap();
     assert_eq!(character.get(armor), 10);
 }
 
@@ -45,3 +45,68 @@ fn conditional_item() {
     character.set_base(burden, 21);
     assert_eq!(character.get(initiative), -2);
 }
+
+#[test]
+fn conditional_item_initially_active() {
+    let mut model = Model::new();
+    let level = model.add_value("level", Value::new(1));
+    let hit_points = model.add_value("hit_points", Value::new(8));
+
+    let novice = model.add_item(
+        "novice",
+        Item::new().set_condition(Calculation::lt(level.into(), 3)),
+    );
+    model.add_modification(
+        novice,
+        hit_points,
+        Modification::new(0, Calculation::placeholder() + 4),
+    );
+
+    let mut character = Character::new(&model);
+
+    assert_eq!(character.get(hit_points), 12);
+    character.set_base(level, 3);
+    assert_eq!(character.get(hit_points), 8);
+}
+
+#[test]
+fn unequip() {
+    let mut model = Model::new();
+
+    let armor = model.add_value("armor", Value::new(0));
+    let chestplate = model.add_item("chestplate", Item::new());
+    model.add_modification(
+        chestplate,
+        armor,
+        Modification::new(0, Calculation::placeholder() + 10),
+    );
+    let overburdened = model.add_item(
+        "overburdened",
+        Item::new().set_condition(Calculation::gt(armor.into(), 20)),
+    );
+
+    let mut character = Character::new(&model);
+
+    character.equip(chestplate).unwrap();
+    character.equip(chestplate).unwrap();
+    assert_eq!(character.get(armor), 20);
+    assert_eq!(character.unequip(chestplate), Ok(()));
+    assert_eq!(character.get(armor), 10);
+    assert_eq!(character.unequip(chestplate), Ok(()));
+    assert_eq!(character.get(armor), 0);
+    assert_eq!(character.equipped(chestplate), 0);
+
+    assert_eq!(
+        character.unequip(chestplate),
+        Err(CharacterError::NotEquipped(chestplate))
+    );
+    assert_eq!(
+        character.unequip(overburdened),
+        Err(CharacterError::Conditional(overburdened))
+    );
+    assert_eq!(
+        character.equip(overburdened),
+        Err(CharacterError::Conditional(overburdened))
+    );
+    assert_eq!(character.equipped(overburdened), 0);
+}
@@ -0,0 +1,153 @@
+use charsheet::model::*;
+use charsheet::{Character, CharacterError, ItemInventory, ItemState};
+
+fn charges(
+    character: &Character,
+    inventory: Option<ItemInventory>,
+    wand: Id<Item>,
+) -> Vec<(u32, u16)> {
+    character
+        .stacks(inventory, wand)
+        .into_iter()
+        .map(|(state, count)| (state.charges.unwrap(), count))
+        .collect()
+}
+
+fn with_charges(charges: u32) -> ItemState {
+    ItemState {
+        charges: Some(charges),
+        ..ItemState::default()
+    }
+}
+
+#[test]
+fn stacks_by_state() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let wand = model.add_item("wand", Item::new().set_physical(1, 10).set_charges(7));
+
+    let mut character = Character::new(&model);
+    character.store(None, wand, 3);
+    assert_eq!(charges(&character, None, wand), vec![(7, 3)]);
+
+    // Changing the state splits an instance off the stack
+    character.set_state(None, wand, 0, with_charges(3)).unwrap();
+    assert_eq!(charges(&character, None, wand), vec![(7, 2), (3, 1)]);
+
+    // Instances in the same state stack again
+    character.set_state(None, wand, 0, with_charges(3)).unwrap();
+    assert_eq!(charges(&character, None, wand), vec![(7, 1), (3, 2)]);
+    character.set_state(None, wand, 0, with_charges(3)).unwrap();
+    assert_eq!(charges(&character, None, wand), vec![(3, 3)]);
+
+    // New instances start with full charges
+    character.store(None, wand, 1);
+    assert_eq!(charges(&character, None, wand), vec![(3, 3), (7, 1)]);
+
+    character.undo();
+    character.undo();
+    assert_eq!(charges(&character, None, wand), vec![(7, 1), (3, 2)]);
+}
+
+#[test]
+fn invalid_states() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let case = model.add_inventory("case", Inventory::new().slots(1));
+    let wand = model.add_item("wand", Item::new().set_physical(1, 10).set_charges(7));
+    let sword = model.add_item("sword", Item::new().set_physical(3, 1).set_durability(50));
+    let case = model.add_item(
+        "wand_case",
+        Item::new().set_physical(1, 1).set_inventory(case),
+    );
+
+    let mut character = Character::new(&model);
+    character.store(None, wand, 1);
+    character.store(None, sword, 1);
+
+    assert_eq!(
+        character.set_state(None, wand, 0, with_charges(8)),
+        Err(CharacterError::InvalidState(wand))
+    );
+    assert_eq!(
+        character.set_state(None, sword, 0, with_charges(1)),
+        Err(CharacterError::InvalidState(sword))
+    );
+    assert_eq!(
+        character.set_state(None, wand, 1, with_charges(1)),
+        Err(CharacterError::NotEnough {
+            item: wand,
+            missing: 1
+        })
+    );
+
+    // Splitting a stack needs a free slot
+    character.store(None, case, 1);
+    let case = character.containers(None, case)[0];
+    character.store(Some(case), wand, 2);
+    assert_eq!(
+        character.set_state(Some(case), wand, 0, with_charges(1)),
+        Err(CharacterError::DoesNotFit {
+            item: wand,
+            rest: 1
+        })
+    );
+    character.take(Some(case), wand, 1);
+    character
+        .set_state(Some(case), wand, 0, with_charges(1))
+        .unwrap();
+    assert_eq!(charges(&character, Some(case), wand), vec![(1, 1)]);
+}
+
+#[test]
+fn custom_names() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let sword = model.add_item("sword", Item::new().set_physical(3, 1).set_durability(50));
+
+    let mut character = Character::new(&model);
+    character.store(None, sword, 2);
+
+    let sting = ItemState {
+        durability: Some(42),
+        front_end: Some(FrontEnd::new("Sting")),
+        notes: "Glows blue\nwhen orcs are near".to_string(),
+        ..ItemState::default()
+    };
+    character.set_state(None, sword, 1, sting.clone()).unwrap();
+
+    let stacks = character.stacks(None, sword);
+    assert_eq!(stacks.len(), 2);
+    assert_eq!(stacks[0].0, &ItemState::new(model.items().get(sword)));
+    assert_eq!(stacks[1], (&sting, 1));
+}
+
+#[test]
+fn transfer_keeps_state() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let case = model.add_inventory("case", Inventory::new().slots(1));
+    let wand = model.add_item("wand", Item::new().set_physical(1, 10).set_charges(7));
+    let case = model.add_item(
+        "wand_case",
+        Item::new().set_physical(1, 1).set_inventory(case),
+    );
+
+    let mut character = Character::new(&model);
+    character.store(None, case, 1);
+    let case = character.containers(None, case)[0];
+    character.store(None, wand, 3);
+    character.set_state(None, wand, 0, with_charges(2)).unwrap();
+
+    // Instances are moved starting with the last stack
+    assert_eq!(character.transfer(None, Some(case), wand, 3), 1);
+    assert_eq!(charges(&character, Some(case), wand), vec![(2, 1)]);
+    assert_eq!(charges(&character, None, wand), vec![(7, 2)]);
+
+    assert_eq!(character.transfer(Some(case), None, wand, 1), 1);
+    assert_eq!(charges(&character, None, wand), vec![(7, 2), (2, 1)]);
+}
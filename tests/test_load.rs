@@ -0,0 +1,78 @@
+use charsheet::model::*;
+use charsheet::Character;
+
+#[test]
+fn burden_from_fill() {
+    let mut model = Model::new();
+    let burden = model.add_value("burden", Value::new(0));
+    let speed = model.add_value("speed", Value::new(30));
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    model.add_load(burden, main, Load::Fill);
+    let torch = model.add_item("torch", Item::new().set_physical(2, 5));
+    let anvil = model.add_item("anvil", Item::new().set_physical(30, 1));
+    let overburdened = model.add_item(
+        "overburdened",
+        Item::new().set_condition(Calculation::gt(burden.into(), 20)),
+    );
+    model.add_modification(
+        overburdened,
+        speed,
+        Modification::new(0, Calculation::placeholder() - 10),
+    );
+
+    let mut character = Character::new(&model);
+    character.store(None, torch, 4);
+    assert_eq!(character.get(burden), 8);
+    assert_eq!(character.equipped(overburdened), 0);
+
+    character.store(None, anvil, 1);
+    assert_eq!(character.get(burden), 38);
+    assert_eq!(character.equipped(overburdened), 1);
+    assert_eq!(character.get(speed), 20);
+
+    character.take(None, anvil, 1);
+    assert_eq!(character.get(speed), 30);
+
+    character.undo();
+    assert_eq!(character.get(burden), 38);
+    assert_eq!(character.get(speed), 20);
+
+    // The base value still applies on top of the load
+    character.set_base(burden, -20);
+    assert_eq!(character.get(burden), 18);
+    assert_eq!(character.equipped(overburdened), 0);
+}
+
+#[test]
+fn carried_inventories() {
+    let mut model = Model::new();
+    let burden = model.add_value("burden", Value::new(0));
+    let used_slots = model.add_value("used_slots", Value::new(0));
+    let main = model.add_inventory("main", Inventory::new().slots(10));
+    model.set_main_inventory(main);
+    let small = model.add_inventory("small", Inventory::new().weight_percent(50));
+    model.add_load(burden, main, Load::Fill);
+    model.add_load(used_slots, main, Load::Slots);
+    model.add_load(used_slots, small, Load::Slots);
+    let pouch = model.add_item("pouch", Item::new().set_physical(1, 1).set_inventory(small));
+    let torch = model.add_item("torch", Item::new().set_physical(2, 5));
+
+    let mut character = Character::new(&model);
+    character.store(None, pouch, 2);
+    let pouches = character.containers(None, pouch);
+    character.store(Some(pouches[0]), torch, 6);
+    assert_eq!(character.get(burden), 8);
+    assert_eq!(character.get(used_slots), 4);
+
+    character.store(Some(pouches[1]), torch, 1);
+    assert_eq!(character.get(used_slots), 5);
+
+    // Inventories of dropped containers are no longer carried
+    character.take(None, pouch, 1);
+    assert_eq!(character.get(burden), 7);
+    assert_eq!(character.get(used_slots), 3);
+
+    let explanation = character.explain(used_slots);
+    assert_eq!((explanation.base, explanation.load), (0, 3));
+}
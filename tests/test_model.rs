@@ -0,0 +1,249 @@
+use charsheet::dice::Dice;
+use charsheet::model::*;
+
+#[test]
+fn duplicate_ids() {
+    let mut model = Model::new();
+
+    model.add_value("strength", Value::new(0));
+    assert_eq!(
+        model.try_add_value("strength", Value::new(0)),
+        Err(ModelError::DuplicateId("strength".to_owned()))
+    );
+
+    // Different kinds of entities do not alias
+    assert!(model.try_add_item("strength", Item::new()).is_ok());
+    assert!(model.try_add_item("strength", Item::new()).is_err());
+    assert!(model.try_add_choice("race", Choice::new()).is_ok());
+    assert!(model.try_add_choice("race", Choice::new()).is_err());
+    assert!(model.try_add_inventory("main", Inventory::new()).is_ok());
+    assert!(model.try_add_inventory("main", Inventory::new()).is_err());
+}
+
+#[test]
+fn unknown_ids() {
+    let mut model = Model::new();
+    let strength = model.add_value("strength", Value::new(0));
+
+    assert_eq!(model.values().try_id("strength"), Ok(strength));
+    assert_eq!(
+        model.values().try_id("dexterity"),
+        Err(ModelError::UnknownId("dexterity".to_owned()))
+    );
+    assert!(model.values().try_get(strength).is_some());
+    assert!(Model::new().values().try_get(strength).is_none());
+}
+
+#[test]
+fn invalid_ids() {
+    // Every id of the other model is past the end of the containers of `model`
+    let mut other = Model::new();
+    other.add_value("strength", Value::new(0));
+    let burden = other.add_value("burden", Value::new(0));
+    other.add_inventory("main", Inventory::new());
+    let bag = other.add_inventory("bag", Inventory::new());
+    other.add_slot("body", Slot::new(1));
+    let hand = other.add_slot("hand", Slot::new(1));
+    other.add_item("chestplate", Item::new());
+    let sword = other.add_item("sword", Item::new());
+    other.add_resource("ki", Resource::new(1));
+    let hp = other.add_resource("hp", Resource::new(1));
+    other.add_roll("damage", Roll::new(Dice::new(1)));
+    let attack = other.add_roll("attack", Roll::new(Dice::new(1)));
+    other.add_choice("class", Choice::new());
+    let race = other.add_choice("race", Choice::new());
+
+    let mut model = Model::new();
+    let strength = model.add_value("strength", Value::new(0));
+    let main = model.add_inventory("main", Inventory::new());
+    let body = model.add_slot("body", Slot::new(1));
+    let chestplate = model.add_item("chestplate", Item::new());
+    let ki = model.add_resource("ki", Resource::new(1));
+    let damage = model.add_roll("damage", Roll::new(Dice::new(1)));
+    let class = model.add_choice("class", Choice::new());
+
+    let invalid = Some(ModelError::InvalidId);
+    let bonus = || Modification::new(0, Calculation::placeholder() + 1);
+    assert_eq!(
+        model
+            .try_add_inventory("pack", Inventory::new().capacity(burden + 1))
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_item("torch", Item::new().set_condition(burden + 1))
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model.try_add_item("torch", Item::new().occupy(hand)).err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_item("torch", Item::new().set_physical(1, 1).set_inventory(bag))
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_group("health", Group::new().resources(vec![hp]))
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_resource("hp", Resource::new(burden + 1))
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_recovery("rest", Recovery::new().refresh(hp))
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_recovery("rest", Recovery::new().recharge(sword))
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_recovery("rest", Recovery::new().refresh_by(ki, burden + 1))
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_roll("attack", Roll::new(Dice::new(burden)))
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_roll_modifier(sword, damage, RollModifier::Advantage)
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_roll_modifier(chestplate, attack, RollModifier::Advantage)
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model.try_add_dependency(burden, strength + 1).err(),
+        invalid
+    );
+    assert_eq!(
+        model.try_add_dependency(strength, burden + 1).err(),
+        invalid
+    );
+    assert_eq!(model.try_add_load(burden, main, Load::Fill).err(), invalid);
+    assert_eq!(model.try_add_load(strength, bag, Load::Fill).err(), invalid);
+    assert_eq!(
+        model
+            .try_add_selection(race, Selection::new(std::iter::empty()))
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_selection(class, Selection::new(vec![(burden, bonus())].into_iter()))
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_selection(
+                class,
+                Selection::new(std::iter::empty()).roll_modifier(attack, RollModifier::Advantage)
+            )
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model.try_add_modification(sword, strength, bonus()).err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_modification(chestplate, burden, bonus())
+            .err(),
+        invalid
+    );
+    assert_eq!(
+        model
+            .try_add_modification(
+                chestplate,
+                strength,
+                Modification::new(0, Calculation::placeholder() + burden)
+            )
+            .err(),
+        invalid
+    );
+
+    // Failed calls add nothing to the model
+    assert!(model.items().try_id("torch").is_err());
+    assert!(model.recoveries().try_id("rest").is_err());
+    assert!(model
+        .try_add_item(
+            "torch",
+            Item::new().occupy(body).set_condition(strength + 1)
+        )
+        .is_ok());
+}
+
+#[test]
+#[should_panic(expected = "unknown id `dexterity`")]
+fn unknown_id_panics() {
+    Model::new().values().id("dexterity");
+}
+
+#[test]
+fn zero_stack_size() {
+    assert!(Item::new().try_set_physical(1, 1).is_ok());
+    assert_eq!(
+        Item::new().try_set_physical(1, 0).err(),
+        Some(ModelError::ZeroStackSize)
+    );
+}
+
+#[test]
+fn placeholders() {
+    let mut model = Model::new();
+    let strength = model.add_value("strength", Value::new(0));
+
+    assert!(Calculation::placeholder().has_placeholders());
+    assert!(!(strength + 1).has_placeholders());
+
+    assert_eq!(
+        model.try_add_dependency(strength, Calculation::placeholder() + 1),
+        Err(ModelError::Placeholder)
+    );
+    assert_eq!(
+        model.try_add_item(
+            "item",
+            Item::new().set_condition(Calculation::placeholder())
+        ),
+        Err(ModelError::Placeholder)
+    );
+    assert_eq!(
+        model.try_add_inventory("main", Inventory::new().slots(Calculation::placeholder())),
+        Err(ModelError::Placeholder)
+    );
+
+    // Failed attempts did not register anything
+    assert!(model.try_add_item("item", Item::new()).is_ok());
+    assert!(model.try_add_inventory("main", Inventory::new()).is_ok());
+}
+
+#[test]
+fn parse_errors_convert() {
+    let model = Model::new();
+    let err: ModelError = Calculation::parse("1 +", &model).unwrap_err().into();
+    assert_eq!(err.to_string(), "unexpected end of formula at 3..3");
+}
@@ -0,0 +1,168 @@
+use charsheet::model::*;
+use charsheet::Character;
+
+#[test]
+fn store_nested() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new().capacity(40));
+    model.set_main_inventory(main);
+    let pack = model.add_inventory("pack", Inventory::new().capacity(30).slots(3));
+    let backpack = model.add_item(
+        "backpack",
+        Item::new().set_physical(5, 1).set_inventory(pack),
+    );
+    let torch = model.add_item("torch", Item::new().set_physical(2, 5));
+
+    let mut character = Character::new(&model);
+    assert_eq!(character.store(None, backpack, 2), 0);
+    assert_eq!(character.amount(None, backpack), 2);
+    assert_eq!(character.fill(None), 10);
+
+    let packs = character.containers(None, backpack);
+    assert_eq!(packs.len(), 2);
+    assert_ne!(packs[0], packs[1]);
+
+    assert_eq!(character.store(Some(packs[0]), torch, 7), 0);
+    assert_eq!(character.amount(Some(packs[0]), torch), 7);
+    assert_eq!(character.amount(Some(packs[1]), torch), 0);
+    assert_eq!(character.amount(None, torch), 0);
+    assert_eq!(character.fill(Some(packs[0])), 14);
+    assert_eq!(character.fill(None), 24);
+
+    assert_eq!(character.take(Some(packs[0]), torch, 2), 0);
+    assert_eq!(character.fill(None), 20);
+}
+
+#[test]
+fn limits_of_containing_inventories() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new().capacity(40));
+    model.set_main_inventory(main);
+    let pack = model.add_inventory("pack", Inventory::new().capacity(30).slots(3));
+    let backpack = model.add_item(
+        "backpack",
+        Item::new().set_physical(5, 1).set_inventory(pack),
+    );
+    let torch = model.add_item("torch", Item::new().set_physical(2, 5));
+    let anvil = model.add_item("anvil", Item::new().set_physical(30, 1));
+
+    let mut character = Character::new(&model);
+    character.store(None, backpack, 1);
+    character.store(None, anvil, 1);
+    let pack = character.containers(None, backpack)[0];
+
+    // Only 5 of 40 are left in the main inventory
+    assert_eq!(character.store(Some(pack), torch, 5), 3);
+    assert_eq!(character.fill(None), 39);
+    assert!(character
+        .batch(|transaction| transaction.store(Some(pack), torch, 1))
+        .is_err());
+
+    // Slots of the nested inventory still apply
+    character.drop(None, anvil);
+    assert_eq!(character.store(Some(pack), torch, 15), 2);
+    assert_eq!(character.amount(Some(pack), torch), 15);
+}
+
+#[test]
+fn weight_percent() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new().capacity(40));
+    model.set_main_inventory(main);
+    let holding = model.add_inventory("holding", Inventory::new().capacity(100).weight_percent(0));
+    let pouch = model.add_inventory("pouch", Inventory::new().weight_percent(50));
+    let bag = model.add_item(
+        "bag_of_holding",
+        Item::new().set_physical(3, 1).set_inventory(holding),
+    );
+    let pouch = model.add_item("pouch", Item::new().set_physical(1, 1).set_inventory(pouch));
+    let torch = model.add_item("torch", Item::new().set_physical(2, 5));
+    let anvil = model.add_item("anvil", Item::new().set_physical(30, 1));
+
+    let mut character = Character::new(&model);
+    character.store(None, bag, 1);
+    let bag = character.containers(None, bag)[0];
+    assert_eq!(character.store(Some(bag), anvil, 3), 0);
+    assert_eq!(character.fill(Some(bag)), 90);
+    assert_eq!(character.fill(None), 3);
+
+    // Nested twice, half of the pouch counts towards the bag
+    character.store(Some(bag), pouch, 1);
+    let pouch = character.containers(Some(bag), pouch)[0];
+    assert_eq!(character.store(Some(pouch), torch, 5), 0);
+    assert_eq!(character.fill(Some(pouch)), 10);
+    assert_eq!(character.fill(Some(bag)), 96);
+    assert_eq!(character.store(Some(pouch), torch, 5), 1);
+    assert_eq!(character.fill(Some(bag)), 100);
+    assert_eq!(character.fill(None), 3);
+}
+
+#[test]
+fn remove_container() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let pack = model.add_inventory("pack", Inventory::new());
+    let backpack = model.add_item(
+        "backpack",
+        Item::new().set_physical(5, 1).set_inventory(pack),
+    );
+    let torch = model.add_item("torch", Item::new().set_physical(2, 5));
+
+    let mut character = Character::new(&model);
+    character.store(None, backpack, 1);
+    let pack = character.containers(None, backpack)[0];
+    character.store(Some(pack), torch, 4);
+    assert_eq!(character.fill(None), 13);
+
+    // The content goes along with the container
+    assert_eq!(character.take(None, backpack, 1), 0);
+    assert_eq!(character.fill(None), 0);
+
+    character.undo();
+    assert_eq!(character.fill(None), 13);
+    assert_eq!(character.containers(None, backpack), vec![pack]);
+    assert_eq!(character.amount(Some(pack), torch), 4);
+
+    // Undoing a nested change updates the containing inventory
+    character.undo();
+    assert_eq!(character.fill(None), 5);
+}
+
+#[test]
+fn undo_new_containers() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let pack = model.add_inventory("pack", Inventory::new());
+    let backpack = model.add_item(
+        "backpack",
+        Item::new().set_physical(5, 1).set_inventory(pack),
+    );
+    let torch = model.add_item("torch", Item::new().set_physical(2, 5));
+
+    let mut character = Character::new(&model);
+    character.store(None, backpack, 1);
+    let pack = character.containers(None, backpack)[0];
+    character.store(Some(pack), torch, 2);
+
+    character.undo();
+    character.undo();
+    assert!(character.containers(None, backpack).is_empty());
+    character.redo();
+    character.redo();
+    assert_eq!(character.containers(None, backpack), vec![pack]);
+    assert_eq!(character.amount(Some(pack), torch), 2);
+
+    // Inventories of undone and rolled back containers are removed, so their handles are reused
+    character.undo();
+    character.undo();
+    let result = character.batch(|transaction| {
+        transaction.store(None, backpack, 1)?;
+        transaction.take(None, torch, 1)
+    });
+    assert!(result.is_err());
+    character.store(None, backpack, 1);
+    assert_eq!(character.containers(None, backpack), vec![pack]);
+    assert_eq!(character.amount(Some(pack), torch), 0);
+}
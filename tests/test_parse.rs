@@ -0,0 +1,126 @@
+use charsheet::model::*;
+use charsheet::Character;
+
+fn model() -> Model {
+    let mut model = Model::new();
+    model.add_value("strength", Value::new(15));
+    model.add_value("level", Value::new(3));
+    model.add_value("result", Value::new(0));
+    model
+}
+
+fn eval(formula: &str) -> i32 {
+    let mut model = model();
+    let result = model.values().id("result");
+    let calc = Calculation::parse(formula, &model).unwrap();
+    model.add_dependency(result, calc);
+
+    Character::new(&model).get(result)
+}
+
+fn error(formula: &str) -> ParseError {
+    Calculation::parse(formula, &model()).err().unwrap()
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(eval("1 + 2 * 3"), 7);
+    assert_eq!(eval("(1 + 2) * 3"), 9);
+    assert_eq!(eval("10 - 4 - 3"), 3);
+    assert_eq!(eval("17 % 5"), 2);
+    assert_eq!(eval("-3 + -(2 * 2)"), -7);
+}
+
+#[test]
+fn values() {
+    assert_eq!(eval("strength / 2 - 5"), 2);
+    assert_eq!(eval("floor(strength / 2) - 5"), 2);
+    assert_eq!(eval("ceil(strength / 2) - 5"), 3);
+    assert_eq!(eval("round(level / 2)"), 2);
+    assert_eq!(eval("`strength` + level"), 18);
+}
+
+#[test]
+fn float_factors() {
+    assert_eq!(eval("level * 1.5"), 4);
+    assert_eq!(eval("ceil(1.5 * level)"), 5);
+    assert_eq!(eval("round(level * -1.5)"), -5);
+}
+
+#[test]
+fn functions_and_logic() {
+    assert_eq!(eval("min(strength, 10) + max(level, 5)"), 15);
+    assert_eq!(eval("abs(level - strength)"), 12);
+    assert_eq!(eval("strength > 10 && level <= 3"), 1);
+    assert_eq!(eval("strength == 10 || level != 3"), 0);
+    assert_eq!(eval("!(strength >= 15) + (level < 4)"), 1);
+}
+
+#[test]
+fn placeholder() {
+    let mut model = model();
+    let strength = model.values().id("strength");
+    let belt = model.add_item("belt", Item::new());
+    let calc = Calculation::parse("max(_, 19)", &model).unwrap();
+    model.add_modification(belt, strength, Modification::new(0, calc));
+
+    let mut character = Character::new(&model);
+    character.equip(belt).unwrap();
+    assert_eq!(character.get(strength), 19);
+}
+
+#[test]
+fn errors() {
+    let err = error("strength + dexterity");
+    assert_eq!(
+        err.kind(),
+        &ParseErrorKind::UnknownIdentifier("dexterity".to_owned())
+    );
+    assert_eq!(err.span(), 11..20);
+
+    assert_eq!(error("1 + ").kind(), &ParseErrorKind::UnexpectedEnd);
+    assert_eq!(error("1 + ").span(), 4..4);
+    assert_eq!(error("(1 + 2").kind(), &ParseErrorKind::UnexpectedEnd);
+    assert_eq!(error("1 $ 2").span(), 2..3);
+    assert_eq!(
+        error("1 2").kind(),
+        &ParseErrorKind::UnexpectedToken("2".to_owned())
+    );
+    assert_eq!(
+        error("sqrt(4)").kind(),
+        &ParseErrorKind::UnknownFunction("sqrt".to_owned())
+    );
+    assert_eq!(error("min(1)").span(), 0..6);
+    assert_eq!(
+        error("floor(level)").kind(),
+        &ParseErrorKind::NothingToRound
+    );
+    assert_eq!(error("level + 1.5").span(), 8..11);
+    assert_eq!(error("3000000000").kind(), &ParseErrorKind::InvalidNumber);
+}
+
+#[test]
+fn nesting_limit() {
+    let nested = |depth| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+    assert_eq!(eval(&nested(32)), 1);
+    assert_eq!(error(&nested(33)).kind(), &ParseErrorKind::TooDeep);
+    assert_eq!(error(&nested(33)).span(), 32..33);
+    assert_eq!(error(&nested(200_000)).kind(), &ParseErrorKind::TooDeep);
+
+    assert_eq!(error(&"-".repeat(200_000)).kind(), &ParseErrorKind::TooDeep);
+    assert_eq!(
+        error(&format!(
+            "{}1{}",
+            "abs(".repeat(200_000),
+            ")".repeat(200_000)
+        ))
+        .kind(),
+        &ParseErrorKind::TooDeep
+    );
+
+    // Long chains of operators nest as deeply when evaluated
+    assert_eq!(eval(&vec!["1"; 257].join(" + ")), 257);
+    let chain = vec!["1"; 200_000].join(" + ");
+    assert_eq!(error(&chain).kind(), &ParseErrorKind::TooDeep);
+    assert_eq!(error(&chain).span(), 0..chain.len());
+}
@@ -0,0 +1,124 @@
+use charsheet::model::*;
+use charsheet::{Character, CharacterError, ItemState};
+
+fn build_model() -> Model {
+    let mut model = Model::new();
+
+    let level = model.add_value("level", Value::new(4));
+    let hp = model.add_resource("hp", Resource::new(level * 10));
+    let ki = model.add_resource("ki", Resource::new(level));
+    let wand = model.add_resource("wand_charges", Resource::new(7));
+
+    model.add_recovery("short_rest", Recovery::new().refresh(ki));
+    model.add_recovery(
+        "long_rest",
+        Recovery::new()
+            .refresh_by(hp, level * 5)
+            .refresh(ki)
+            .refresh_by(wand, 3 - level),
+    );
+    model.add_recovery("dawn", Recovery::new().refresh_by(wand, 3));
+
+    model
+}
+
+#[test]
+fn refresh() {
+    let model = build_model();
+    let hp = model.resources().id("hp");
+    let ki = model.resources().id("ki");
+    let wand = model.resources().id("wand_charges");
+
+    let mut character = Character::new(&model);
+    character.spend(hp, 35).unwrap();
+    character.spend(ki, 4).unwrap();
+    character.spend(wand, 5).unwrap();
+
+    character.apply_event("short_rest").unwrap();
+    assert_eq!(character.current(hp), 5);
+    assert_eq!(character.current(ki), 4);
+
+    // Negative amounts restore nothing
+    character.apply_event("long_rest").unwrap();
+    assert_eq!(character.current(hp), 25);
+    assert_eq!(character.current(wand), 2);
+
+    character.apply_event("dawn").unwrap();
+    character.apply_event("dawn").unwrap();
+    assert_eq!(character.current(wand), 7);
+}
+
+#[test]
+fn amounts_use_current_values() {
+    let model = build_model();
+    let level = model.values().id("level");
+    let hp = model.resources().id("hp");
+    let long_rest = model.recoveries().id("long_rest");
+
+    let mut character = Character::new(&model);
+    character.spend(hp, 40).unwrap();
+
+    character
+        .batch(|transaction| {
+            transaction.set_base(level, 6);
+            transaction.recover(long_rest);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(character.current(hp), 30);
+
+    // A recovery is undone as a whole
+    character.undo();
+    assert_eq!(character.current(hp), 0);
+}
+
+#[test]
+fn unknown_event() {
+    let model = build_model();
+    let mut character = Character::new(&model);
+
+    assert_eq!(
+        character.apply_event("full_moon"),
+        Err(CharacterError::UnknownEvent("full_moon".to_string()))
+    );
+}
+
+#[test]
+fn recharge_items() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let level = model.add_value("level", Value::new(1));
+    let wand = model.add_item("wand", Item::new().set_physical(1, 3).set_charges(7));
+    model.add_recovery("dawn", Recovery::new().recharge_by(wand, level + 1));
+    model.add_recovery("long_rest", Recovery::new().recharge(wand));
+
+    let with_charges = |charges| ItemState {
+        charges: Some(charges),
+        ..ItemState::default()
+    };
+    let charges = |character: &Character| -> Vec<_> {
+        character
+            .stacks(None, wand)
+            .into_iter()
+            .map(|(state, count)| (state.charges.unwrap(), count))
+            .collect()
+    };
+
+    let mut character = Character::new(&model);
+    character.store(None, wand, 3);
+    character.set_state(None, wand, 0, with_charges(1)).unwrap();
+    character.set_state(None, wand, 0, with_charges(6)).unwrap();
+    assert_eq!(charges(&character), vec![(7, 1), (1, 1), (6, 1)]);
+
+    // Instances in the same state stack again
+    character.apply_event("dawn").unwrap();
+    assert_eq!(charges(&character), vec![(7, 2), (3, 1)]);
+
+    character.apply_event("long_rest").unwrap();
+    assert_eq!(charges(&character), vec![(7, 3)]);
+
+    character.undo();
+    character.undo();
+    assert_eq!(charges(&character), vec![(7, 1), (1, 1), (6, 1)]);
+}
@@ -0,0 +1,120 @@
+use charsheet::model::*;
+use charsheet::{Character, CharacterError, Event};
+
+#[test]
+fn maximum() {
+    let mut model = Model::new();
+    let level = model.add_value("level", Value::new(2));
+    let constitution = model.add_value("constitution", Value::new(3));
+    let hp = model.add_resource("hp", Resource::new(level * 8 + constitution));
+    let ki = model.add_resource("ki", Resource::new(level - 3));
+
+    let mut character = Character::new(&model);
+    assert_eq!(character.maximum(hp), 19);
+    assert_eq!(character.current(hp), 19);
+
+    // Negative maximums count as zero
+    assert_eq!(character.maximum(ki), 0);
+    character.set_base(level, 5);
+    assert_eq!(character.maximum(ki), 2);
+    assert_eq!(character.maximum(hp), 43);
+
+    // Raising the maximum does not refill the resource
+    assert_eq!(character.current(ki), 0);
+    assert_eq!(character.current(hp), 19);
+}
+
+#[test]
+fn spend_restore() {
+    let mut model = Model::new();
+    let hp = model.add_resource("hp", Resource::new(19));
+
+    let mut character = Character::new(&model);
+    character.spend(hp, 15).unwrap();
+    assert_eq!(character.current(hp), 4);
+
+    assert_eq!(
+        character.spend(hp, 6),
+        Err(CharacterError::Insufficient {
+            resource: hp,
+            missing: 2
+        })
+    );
+    assert_eq!(character.current(hp), 4);
+
+    assert_eq!(character.restore(hp, 10), 10);
+    assert_eq!(character.restore(hp, 10), 5);
+    assert_eq!(character.current(hp), 19);
+}
+
+#[test]
+fn clamp() {
+    let mut model = Model::new();
+    let level = model.add_value("level", Value::new(2));
+    let constitution = model.add_value("constitution", Value::new(3));
+    let hp = model.add_resource("hp", Resource::new(level * 8 + constitution));
+    let tired = model.add_item("tired", Item::new());
+    model.add_modification(
+        tired,
+        constitution,
+        Modification::new(0, Calculation::placeholder() - 2),
+    );
+
+    let mut character = Character::new(&model);
+    character.spend(hp, 1).unwrap();
+    character.equip(tired).unwrap();
+    assert_eq!(character.maximum(hp), 17);
+    assert_eq!(character.current(hp), 17);
+
+    // Unaffected by changes that keep the maximum above the current amount
+    character.unequip(tired).unwrap();
+    character.set_base(level, 3);
+    assert_eq!(character.current(hp), 17);
+
+    // Undoing the change of the maximum also restores the clamped amount
+    character.undo();
+    character.undo();
+    assert_eq!(character.current(hp), 17);
+    character.undo();
+    assert_eq!(character.maximum(hp), 19);
+    assert_eq!(character.current(hp), 18);
+
+    character.redo();
+    assert_eq!(character.current(hp), 17);
+}
+
+#[test]
+fn events() {
+    let mut model = Model::new();
+    let hp = model.add_resource("hp", Resource::new(19));
+    let ki = model.add_resource("ki", Resource::new(2));
+    let group = model.add_group("health", Group::new().resources(vec![hp]));
+
+    let mut character = Character::new(&model);
+    let events = character.subscribe(Some(group));
+
+    character.spend(hp, 19).unwrap();
+    character.spend(ki, 1).unwrap();
+    assert_eq!(
+        character.poll(events),
+        vec![
+            Event::Resource {
+                id: hp,
+                old: 19,
+                new: 0
+            },
+            Event::Depleted(hp),
+        ]
+    );
+
+    character.restore(hp, 1);
+    character.spend(hp, 0).unwrap();
+    assert_eq!(
+        character.poll(events),
+        vec![Event::Resource {
+            id: hp,
+            old: 0,
+            new: 1
+        }]
+    );
+}
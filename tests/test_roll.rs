@@ -0,0 +1,119 @@
+use charsheet::dice::*;
+use charsheet::model::*;
+use charsheet::{Character, ModificationSource};
+
+struct Ids {
+    dexterity: Id<Value>,
+    stealth: Id<Roll>,
+    cloak: Id<Item>,
+    plate: Id<Item>,
+    ring: Id<Item>,
+    background: Id<Choice>,
+}
+
+fn build_model() -> (Model, Ids) {
+    let mut model = Model::new();
+    let dexterity = model.add_value("dexterity", Value::new(3));
+    let proficiency = model.add_value("proficiency", Value::new(2));
+
+    let dice = Dice::parse("1d20 + dexterity", &model).unwrap();
+    let stealth = model.add_roll("stealth", Roll::new(dice).bonus(proficiency));
+
+    let cloak = model.add_item("cloak", Item::new());
+    model.add_roll_modifier(cloak, stealth, RollModifier::Advantage);
+    let plate = model.add_item("plate", Item::new());
+    model.add_roll_modifier(plate, stealth, RollModifier::Disadvantage);
+    let ring = model.add_item("ring", Item::new());
+    model.add_roll_modifier(ring, stealth, RollModifier::Bonus(1.into()));
+
+    let background = model.add_choice("background", Choice::new());
+    model.add_selection(background, Selection::new(std::iter::empty()));
+    let selection = Selection::new(std::iter::empty()).roll_modifier(
+        stealth,
+        RollModifier::Bonus(Calculation::from(dexterity) * 2),
+    );
+    model.add_selection(background, selection);
+
+    let ids = Ids {
+        dexterity,
+        stealth,
+        cloak,
+        plate,
+        ring,
+        background,
+    };
+    (model, ids)
+}
+
+#[test]
+fn plain_roll() {
+    let (model, ids) = build_model();
+    let character = Character::new(&model);
+
+    let result = character.roll(ids.stealth, &mut SplitMix64::new(5));
+    assert_eq!(result.parts.len(), 3);
+    assert_eq!(result.parts[0].dice.len(), 1);
+    assert_eq!(result.parts[1].term, Term::Value(ids.dexterity));
+    assert_eq!(result.parts[2].term, Term::Constant(2));
+    assert_eq!(result.parts[2].source, None);
+
+    let die = result.parts[0].dice[0].total() as i32;
+    assert_eq!(result.total, die + 5);
+}
+
+#[test]
+fn modifiers() {
+    let (model, ids) = build_model();
+    let mut character = Character::new(&model);
+    character.equip(ids.ring).unwrap();
+    character.equip(ids.ring).unwrap();
+    character.select(ids.background, 1).unwrap();
+
+    let result = character.roll(ids.stealth, &mut SplitMix64::new(5));
+    let bonuses: Vec<_> = result.parts[3..]
+        .iter()
+        .map(|part| (part.source, part.total))
+        .collect();
+    assert_eq!(
+        bonuses,
+        vec![
+            (Some(ModificationSource::Item(ids.ring)), 2),
+            (Some(ModificationSource::Choice(ids.background)), 6),
+        ]
+    );
+
+    character.equip(ids.cloak).unwrap();
+    let result = character.roll(ids.stealth, &mut SplitMix64::new(5));
+    let dice = &result.parts[0].dice;
+    assert_eq!(dice.len(), 2);
+    let kept = dice.iter().find(|die| die.kept).unwrap();
+    assert!(dice.iter().all(|die| die.total() <= kept.total()));
+
+    // Advantage and disadvantage cancel out
+    character.equip(ids.plate).unwrap();
+    let result = character.roll(ids.stealth, &mut SplitMix64::new(5));
+    assert_eq!(result.parts[0].dice.len(), 1);
+
+    character.unequip(ids.cloak).unwrap();
+    let result = character.roll(ids.stealth, &mut SplitMix64::new(5));
+    let dice = &result.parts[0].dice;
+    let kept = dice.iter().find(|die| die.kept).unwrap();
+    assert!(dice.iter().all(|die| die.total() >= kept.total()));
+}
+
+#[test]
+fn placeholders() {
+    let (mut model, ids) = build_model();
+
+    let roll = Roll::new(DiceGroup::new(1, 6).into()).bonus(Calculation::placeholder());
+    assert_eq!(
+        model.try_add_roll("damage", roll).err(),
+        Some(ModelError::Placeholder)
+    );
+
+    let modifier = RollModifier::Bonus(Calculation::placeholder() + 1);
+    assert_eq!(
+        model.try_add_roll_modifier(ids.ring, ids.stealth, modifier),
+        Err(ModelError::Placeholder)
+    );
+}
@@ -0,0 +1,397 @@
+use charsheet::model::*;
+use charsheet::{Character, Duration, ItemState, LoadError};
+
+/// Build a model, optionally declaring everything in reverse order.
+fn build_model(reverse: bool) -> Model {
+    let mut model = Model::new();
+
+    let mut values = vec!["strength", "armor", "burden", "initiative"];
+    if reverse {
+        values.reverse();
+    }
+    for id in values {
+        model.add_value(id, Value::new(0));
+    }
+    let armor = model.values().id("armor");
+    let burden = model.values().id("burden");
+    let initiative = model.values().id("initiative");
+    let strength = model.values().id("strength");
+    model.add_dependency(armor, strength / 2);
+
+    let race = model.add_choice("race", Choice::new());
+    for bonus in &[1, 2] {
+        let modification = Modification::new(0, Calculation::placeholder() + *bonus);
+        let selection = Selection::new(vec![(strength, modification)].into_iter());
+        model.add_selection(race, selection);
+    }
+
+    let inventory = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(inventory);
+
+    let mut items = vec!["chestplate", "torch", "overburdened"];
+    if reverse {
+        items.reverse();
+    }
+    for id in items {
+        let item = match id {
+            "chestplate" => Item::new().set_physical(10, 1),
+            "torch" => Item::new().set_physical(1, 5),
+            _ => Item::new().set_condition(Calculation::gt(burden.into(), 10)),
+        };
+        model.add_item(id, item);
+    }
+    model.add_modification(
+        model.items().id("chestplate"),
+        armor,
+        Modification::new(0, Calculation::placeholder() + 5),
+    );
+    model.add_modification(
+        model.items().id("overburdened"),
+        initiative,
+        Modification::new(0, Calculation::placeholder() - 2),
+    );
+
+    model
+}
+
+#[test]
+fn save_and_load() {
+    let model = build_model(false);
+    let chestplate = model.items().id("chestplate");
+    let torch = model.items().id("torch");
+
+    let mut character = Character::new(&model);
+    character.select(model.choices().id("race"), 1).unwrap();
+    character.set_base(model.values().id("strength"), 10);
+    character.set_base(model.values().id("burden"), 12);
+    character.store(None, chestplate, 1);
+    character.equip(chestplate).unwrap();
+    character.store(None, torch, 3);
+
+    let mut save = Vec::new();
+    character.save(&mut save).unwrap();
+    let save = String::from_utf8(save).unwrap();
+    assert!(save.contains("base 10 strength\n"));
+    assert!(save.contains("select 1 race\n"));
+    assert!(save.contains("equip 1 chestplate\n"));
+    assert!(save.contains("store 3 torch\n"));
+    assert!(!save.contains("overburdened"));
+
+    let reordered = build_model(true);
+    for model in &[&model, &reordered] {
+        let loaded = Character::load(model, save.as_bytes()).unwrap();
+        let get = |id| loaded.get(model.values().id(id));
+
+        assert_eq!(get("strength"), 12);
+        assert_eq!(get("armor"), 11);
+        assert_eq!(get("initiative"), -2);
+
+        let mut again = Vec::new();
+        loaded.save(&mut again).unwrap();
+        let mut lines: Vec<_> = String::from_utf8(again)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect();
+        let mut expected: Vec<_> = save.lines().map(String::from).collect();
+        lines.sort();
+        expected.sort();
+        assert_eq!(lines, expected);
+    }
+}
+
+#[test]
+fn load_errors() {
+    let model = build_model(false);
+    let load = |source: &str| Character::load(&model, source.as_bytes()).err().unwrap();
+
+    match load("# comment\n\nbase 1 dexterity") {
+        LoadError::UnknownId { line, id } => {
+            assert_eq!(line, 3);
+            assert_eq!(id, "dexterity");
+        }
+        err => panic!("unexpected error {}", err),
+    }
+    assert!(matches!(
+        load("base x strength"),
+        LoadError::Syntax { line: 1 }
+    ));
+    assert!(matches!(
+        load("wield 1 torch"),
+        LoadError::Syntax { line: 1 }
+    ));
+    assert!(matches!(
+        load("select 2 race"),
+        LoadError::Invalid { line: 1 }
+    ));
+    assert!(matches!(
+        load("equip 1 overburdened"),
+        LoadError::Invalid { line: 1 }
+    ));
+    assert!(matches!(
+        load("store 6 torch"),
+        LoadError::Invalid { line: 1 }
+    ));
+}
+
+#[test]
+fn resources() {
+    let mut model = Model::new();
+    let hp = model.add_resource("hp", Resource::new(19));
+
+    let mut character = Character::new(&model);
+    character.spend(hp, 7).unwrap();
+
+    let mut save = Vec::new();
+    character.save(&mut save).unwrap();
+    let loaded = Character::load(&model, save.as_slice()).unwrap();
+    assert_eq!(loaded.current(hp), 12);
+}
+
+#[test]
+fn effects() {
+    let mut model = Model::new();
+    let attack = model.add_value("attack", Value::new(3));
+    let bless = model.add_item("bless", Item::new());
+    model.add_modification(
+        bless,
+        attack,
+        Modification::new(0, Calculation::placeholder() + 2),
+    );
+    let poisoned = model.add_item("poisoned", Item::new());
+    model.add_modification(
+        poisoned,
+        attack,
+        Modification::new(0, Calculation::placeholder() - 1),
+    );
+    let long_rest = model.add_recovery("long_rest", Recovery::new());
+
+    let mut character = Character::new(&model);
+    character.apply_effect(bless, Duration::Rounds(4)).unwrap();
+    character
+        .apply_effect(poisoned, Duration::UntilEvent(long_rest))
+        .unwrap();
+
+    let mut save = Vec::new();
+    character.save(&mut save).unwrap();
+    assert_eq!(
+        String::from_utf8(save.clone()).unwrap(),
+        "base 3 attack\neffect 4 bless\neffect 0 poisoned\nuntil 0 long_rest\n"
+    );
+    let mut loaded = Character::load(&model, save.as_slice()).unwrap();
+    assert_eq!(loaded.get(attack), 4);
+    assert_eq!(loaded.effects(), character.effects());
+
+    loaded.advance_time(4);
+    loaded.apply_event("long_rest").unwrap();
+    assert_eq!(loaded.get(attack), 3);
+    assert_eq!(loaded.equipped(bless), 0);
+
+    let load = |text: &str| Character::load(&model, text.as_bytes());
+    assert!(load("until 0 long_rest").is_err());
+    assert!(load("effect 0 bless").is_err());
+    assert!(load("effect 0 bless\nuntil 0 bless").is_err());
+    assert!(load("effect 0 bless\nuntil 1 long_rest").is_err());
+    assert!(load("effect 0 bless\nbase 1 attack\nuntil 0 long_rest").is_err());
+    assert!(load("effect 2 bless\nuntil 0 long_rest").is_err());
+}
+
+#[test]
+fn nested_inventories() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let pack = model.add_inventory("pack", Inventory::new());
+    let small = model.add_inventory("small", Inventory::new().weight_percent(50));
+    let backpack = model.add_item(
+        "backpack",
+        Item::new().set_physical(5, 1).set_inventory(pack),
+    );
+    let pouch = model.add_item("pouch", Item::new().set_physical(1, 1).set_inventory(small));
+    let torch = model.add_item("torch", Item::new().set_physical(2, 5));
+
+    let mut character = Character::new(&model);
+    character.store(None, backpack, 1);
+    character.store(None, torch, 1);
+    let pack = character.containers(None, backpack)[0];
+    character.store(Some(pack), pouch, 1);
+    character.store(Some(pack), torch, 1);
+    let inner = character.containers(Some(pack), pouch)[0];
+    character.store(Some(inner), torch, 4);
+
+    let mut save = Vec::new();
+    character.save(&mut save).unwrap();
+    assert_eq!(
+        String::from_utf8(save.clone()).unwrap(),
+        "store 1 backpack\n>store 1 pouch\n>>store 4 torch\n>store 1 torch\nstore 1 torch\n"
+    );
+
+    let loaded = Character::load(&model, save.as_slice()).unwrap();
+    assert_eq!(loaded.fill(None), character.fill(None));
+    let pack = loaded.containers(None, backpack)[0];
+    let inner = loaded.containers(Some(pack), pouch)[0];
+    assert_eq!(loaded.amount(Some(inner), torch), 4);
+    assert_eq!(loaded.fill(Some(pack)), 7);
+
+    assert!(Character::load(&model, ">store 1 torch".as_bytes()).is_err());
+    assert!(Character::load(&model, "store 2 backpack".as_bytes()).is_err());
+    assert!(Character::load(&model, ">base 1 torch".as_bytes()).is_err());
+}
+
+#[test]
+fn loads() {
+    let mut model = Model::new();
+    let burden = model.add_value("burden", Value::new(0));
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    model.add_load(burden, main, Load::Fill);
+    let anvil = model.add_item("anvil", Item::new().set_physical(30, 1));
+    let overburdened = model.add_item(
+        "overburdened",
+        Item::new().set_condition(Calculation::gt(burden.into(), 20)),
+    );
+
+    let mut character = Character::new(&model);
+    character.store(None, anvil, 1);
+
+    let mut save = Vec::new();
+    character.save(&mut save).unwrap();
+    let loaded = Character::load(&model, save.as_slice()).unwrap();
+    assert_eq!(loaded.get(burden), 30);
+    assert_eq!(loaded.equipped(overburdened), 1);
+}
+
+#[test]
+fn item_states() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let case = model.add_inventory("case", Inventory::new().slots(1));
+    let wand = model.add_item("wand", Item::new().set_physical(1, 10).set_charges(7));
+    let sword = model.add_item("sword", Item::new().set_physical(3, 1).set_durability(50));
+    let case = model.add_item(
+        "wand_case",
+        Item::new().set_physical(1, 1).set_inventory(case),
+    );
+    let with_charges = |charges| ItemState {
+        charges: Some(charges),
+        ..ItemState::default()
+    };
+
+    let mut character = Character::new(&model);
+    character.store(None, case, 1);
+    let inner = character.containers(None, case)[0];
+    character.store(Some(inner), wand, 1);
+    character
+        .set_state(Some(inner), wand, 0, with_charges(5))
+        .unwrap();
+    character.store(None, sword, 1);
+
+    let mut front_end = FrontEnd::new("Sting");
+    front_end.description = Some("An elven blade".to_string());
+    let sting = ItemState {
+        durability: Some(50),
+        front_end: Some(front_end),
+        notes: "Found in a troll cave\\\nGlows blue".to_string(),
+        ..ItemState::default()
+    };
+    character.set_state(None, sword, 0, sting.clone()).unwrap();
+
+    let mut save = Vec::new();
+    character.save(&mut save).unwrap();
+    assert_eq!(
+        String::from_utf8(save.clone()).unwrap(),
+        "store 1 wand_case\n\
+         >store 1 wand\n\
+         >charges 5 wand\n\
+         store 1 sword\n\
+         text 0 Sting\n\
+         text 2 An elven blade\n\
+         text 3 Found in a troll cave\\\\\\nGlows blue\n"
+    );
+
+    let loaded = Character::load(&model, save.as_slice()).unwrap();
+    let inner = loaded.containers(None, case)[0];
+    assert_eq!(
+        loaded.stacks(Some(inner), wand),
+        vec![(&with_charges(5), 1)]
+    );
+    assert_eq!(loaded.stacks(None, sword), vec![(&sting, 1)]);
+
+    let load = |text: &str| Character::load(&model, text.as_bytes());
+    assert!(load("charges 3 wand").is_err());
+    assert!(load("store 1 wand\ncharges 8 wand").is_err());
+    assert!(load("store 1 wand\ncharges 3 sword").is_err());
+    assert!(load("store 1 wand\ndurability 3 wand").is_err());
+    assert!(load("store 1 wand\ntext 1 W").is_err());
+    assert!(load("store 1 wand\ntext 3 a\\b").is_err());
+    assert!(load("store 1 wand\n>charges 3 wand").is_err());
+}
+
+#[test]
+fn item_state_whitespace() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let sword = model.add_item("sword", Item::new().set_physical(3, 1));
+
+    let mut front_end = FrontEnd::new("");
+    front_end.name_short = Some("  ".to_string());
+    front_end.description = Some(" An elven blade\t".to_string());
+    let state = ItemState {
+        front_end: Some(front_end),
+        notes: "\u{a0}".to_string(),
+        ..ItemState::default()
+    };
+
+    let mut character = Character::new(&model);
+    character.store(None, sword, 1);
+    character.set_state(None, sword, 0, state.clone()).unwrap();
+
+    let mut save = Vec::new();
+    character.save(&mut save).unwrap();
+    assert_eq!(
+        String::from_utf8(save.clone()).unwrap(),
+        "store 1 sword\n\
+         text 0\n\
+         text 1 \\s\\s\n\
+         text 2 \\sAn elven blade\\t\n\
+         text 3 \\u{a0}\n"
+    );
+    let loaded = Character::load(&model, save.as_slice()).unwrap();
+    assert_eq!(loaded.stacks(None, sword), vec![(&state, 1)]);
+
+    let load = |text: &str| Character::load(&model, text.as_bytes());
+    assert!(load("store 1 sword\ntext 3 \\u{a0").is_err());
+    assert!(load("store 1 sword\ntext 3 \\u{110000}").is_err());
+    assert!(load("store 1 sword\nstore 1").is_err());
+}
+
+#[test]
+fn equipped_items() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let finger = model.add_slot("finger", Slot::new(2));
+    let ring = model.add_item("ring", Item::new().set_physical(1, 10).occupy(finger));
+    let torch = model.add_item("torch", Item::new().set_physical(1, 5));
+
+    let load = |source: &str| Character::load(&model, source.as_bytes());
+    let loaded = load("equip 1 torch\nstore 2 torch\nstore 2 ring\nequip 2 ring").unwrap();
+    assert_eq!(loaded.equipped(torch), 1);
+    assert_eq!(loaded.equipped(ring), 2);
+
+    let error = |source: &str| load(source).err().unwrap();
+    assert!(matches!(
+        error("store 1 ring\nequip 65537 ring"),
+        LoadError::Invalid { line: 2 }
+    ));
+    assert!(matches!(
+        error("equip 2 torch\nstore 1 torch"),
+        LoadError::Invalid { line: 1 }
+    ));
+    assert!(matches!(
+        error("store 3 ring\nequip 3 ring"),
+        LoadError::Invalid { line: 2 }
+    ));
+}
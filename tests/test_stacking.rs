@@ -0,0 +1,187 @@
+use charsheet::model::*;
+use charsheet::{Character, ModificationSource};
+
+fn bonus(kind: &str, amount: i32) -> Modification {
+    Modification::new(0, Calculation::placeholder() + amount)
+        .set_stacking(Stacking::Typed(kind.to_string()))
+}
+
+fn build_model() -> Model {
+    let mut model = Model::new();
+
+    let armor_class = model.add_value("armor_class", Value::new(10));
+    let dexterity = model.add_value("dexterity", Value::new(0));
+
+    let leather = model.add_item("leather", Item::new());
+    model.add_modification(leather, armor_class, bonus("armor", 2));
+    let chain_shirt = model.add_item("chain_shirt", Item::new());
+    model.add_modification(chain_shirt, armor_class, bonus("armor", 4));
+    let shield = model.add_item("shield", Item::new());
+    model.add_modification(shield, armor_class, bonus("shield", 2));
+    let enhancement = model.add_item("enhancement", Item::new());
+    model.add_modification(enhancement, armor_class, bonus("enhancement", 1));
+
+    let ring = model.add_item("ring", Item::new());
+    model.add_modification(
+        ring,
+        armor_class,
+        Modification::new(0, Calculation::placeholder() + 1).set_stacking(Stacking::Once),
+    );
+    let dodge = model.add_item("dodge", Item::new());
+    model.add_modification(
+        dodge,
+        armor_class,
+        Modification::new(0, Calculation::placeholder() + 1),
+    );
+
+    let barkskin = model.add_item("barkskin", Item::new());
+    model.add_modification(
+        barkskin,
+        armor_class,
+        Modification::new(1, 16 + dexterity).set_stacking(Stacking::AtLeast),
+    );
+    let slowed = model.add_item("slowed", Item::new());
+    model.add_modification(
+        slowed,
+        dexterity,
+        Modification::new(0, Calculation::from(0)).set_stacking(Stacking::AtMost),
+    );
+
+    model
+}
+
+#[test]
+fn highest_of_type() {
+    let model = build_model();
+    let armor_class = model.values().id("armor_class");
+    let leather = model.items().id("leather");
+    let chain_shirt = model.items().id("chain_shirt");
+    let shield = model.items().id("shield");
+    let enhancement = model.items().id("enhancement");
+
+    let mut character = Character::new(&model);
+    character.equip(leather).unwrap();
+    assert_eq!(character.get(armor_class), 12);
+    character.equip(chain_shirt).unwrap();
+    assert_eq!(character.get(armor_class), 14);
+    character.unequip(chain_shirt).unwrap();
+    assert_eq!(character.get(armor_class), 12);
+    character.equip(chain_shirt).unwrap();
+
+    // Different types stack, but not with themselves
+    character.equip(shield).unwrap();
+    character.equip(shield).unwrap();
+    assert_eq!(character.get(armor_class), 16);
+    character.equip(enhancement).unwrap();
+    assert_eq!(character.get(armor_class), 17);
+}
+
+#[test]
+fn once_and_stack() {
+    let model = build_model();
+    let armor_class = model.values().id("armor_class");
+    let ring = model.items().id("ring");
+    let dodge = model.items().id("dodge");
+
+    let mut character = Character::new(&model);
+    character.equip(ring).unwrap();
+    character.equip(ring).unwrap();
+    assert_eq!(character.get(armor_class), 11);
+
+    character.equip(dodge).unwrap();
+    character.equip(dodge).unwrap();
+    assert_eq!(character.get(armor_class), 13);
+}
+
+#[test]
+fn bounds() {
+    let model = build_model();
+    let armor_class = model.values().id("armor_class");
+    let dexterity = model.values().id("dexterity");
+    let chain_shirt = model.items().id("chain_shirt");
+    let shield = model.items().id("shield");
+    let barkskin = model.items().id("barkskin");
+    let slowed = model.items().id("slowed");
+
+    let mut character = Character::new(&model);
+    character.set_base(dexterity, 2);
+    character.equip(barkskin).unwrap();
+    assert_eq!(character.get(armor_class), 18);
+
+    character.equip(chain_shirt).unwrap();
+    character.equip(shield).unwrap();
+    assert_eq!(character.get(armor_class), 18);
+
+    character.equip(slowed).unwrap();
+    assert_eq!(character.get(dexterity), 0);
+    assert_eq!(character.get(armor_class), 16);
+
+    character.set_base(dexterity, -1);
+    assert_eq!(character.get(dexterity), -1);
+    assert_eq!(character.get(armor_class), 16);
+}
+
+#[test]
+fn explain_suppressed() {
+    let model = build_model();
+    let armor_class = model.values().id("armor_class");
+    let leather = model.items().id("leather");
+    let chain_shirt = model.items().id("chain_shirt");
+
+    let mut character = Character::new(&model);
+    character.equip(leather).unwrap();
+    character.equip(chain_shirt).unwrap();
+
+    let explanation = character.explain(armor_class);
+    assert_eq!(explanation.actual, 14);
+
+    let steps: Vec<_> = explanation
+        .modifications
+        .iter()
+        .map(|step| (step.source, step.count, step.before, step.after))
+        .collect();
+    assert_eq!(
+        steps,
+        vec![
+            (ModificationSource::Item(leather), 0, 10, 10),
+            (ModificationSource::Item(chain_shirt), 1, 10, 14),
+        ]
+    );
+}
+
+#[test]
+fn typed_priorities() {
+    let mut model = Model::new();
+    let value = model.add_value("value", Value::new(10));
+    let small = model.add_item("small", Item::new());
+    model.add_modification(small, value, bonus("armor", 1));
+    let double = model.add_item("double", Item::new());
+    model.add_modification(
+        double,
+        value,
+        Modification::new(1, Calculation::placeholder() * 2),
+    );
+    let large = model.add_item("large", Item::new());
+    model.add_modification(
+        large,
+        value,
+        Modification::new(2, Calculation::placeholder() + 3)
+            .set_stacking(Stacking::Typed("armor".to_string())),
+    );
+
+    let mut character = Character::new(&model);
+    character.equip(small).unwrap();
+    character.equip(double).unwrap();
+    assert_eq!(character.get(value), 22);
+
+    // The winner applies at its own priority, after the untyped modification
+    character.equip(large).unwrap();
+    assert_eq!(character.get(value), 23);
+    let steps: Vec<_> = character
+        .explain(value)
+        .modifications
+        .iter()
+        .map(|step| (step.count, step.before, step.after))
+        .collect();
+    assert_eq!(steps, vec![(0, 10, 10), (1, 10, 20), (1, 20, 23)]);
+}
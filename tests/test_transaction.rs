@@ -0,0 +1,139 @@
+use charsheet::model::*;
+use charsheet::{Character, CharacterError, Event};
+
+struct Ids {
+    strength: Id<Value>,
+    level: Id<Value>,
+    health: Id<Value>,
+    class: Id<Choice>,
+    sword: Id<Item>,
+}
+
+fn build_model() -> (Model, Ids) {
+    let mut model = Model::new();
+
+    let strength = model.add_value("strength", Value::new(10));
+    let level = model.add_value("level", Value::new(1));
+    let health = model.add_value("health", Value::new(0));
+    model.add_dependency(health, 10 * level);
+    model.add_dependency(health, strength / 2);
+
+    let class = model.add_choice("class", Choice::new());
+    model.add_selection(class, Selection::new(std::iter::empty()));
+    let modification = Modification::new(0, Calculation::placeholder() + 5);
+    model.add_selection(
+        class,
+        Selection::new(vec![(health, modification)].into_iter()),
+    );
+
+    let inventory = Inventory::new().capacity(Calculation::from(strength) * 2);
+    let inventory = model.add_inventory("main", inventory);
+    model.set_main_inventory(inventory);
+    let sword = model.add_item("sword", Item::new().set_physical(5, 1));
+    model.add_modification(
+        sword,
+        strength,
+        Modification::new(0, Calculation::placeholder() + 1),
+    );
+
+    let ids = Ids {
+        strength,
+        level,
+        health,
+        class,
+        sword,
+    };
+    (model, ids)
+}
+
+#[test]
+fn batch_commits() {
+    let (model, ids) = build_model();
+    let mut character = Character::new(&model);
+    let events = character.subscribe(None);
+
+    let health = character
+        .batch(|tx| {
+            tx.set_base(ids.level, 2);
+            tx.set_base(ids.strength, 12);
+            tx.select(ids.class, 1)?;
+            tx.store(None, ids.sword, 1)?;
+            tx.equip(ids.sword)?;
+            Ok(tx.get(ids.health))
+        })
+        .unwrap();
+
+    assert_eq!(health, 31);
+    assert_eq!(character.get(ids.health), 31);
+    assert_eq!(character.selection(ids.class), 1);
+    assert_eq!(character.equipped(ids.sword), 1);
+
+    // One event per change, from the state before the batch
+    let events = character.poll(events);
+    assert!(events.contains(&Event::Value {
+        id: ids.health,
+        old: 15,
+        new: 31
+    }));
+    assert_eq!(events.len(), 5);
+}
+
+#[test]
+fn batch_rolls_back() {
+    let (model, ids) = build_model();
+    let mut character = Character::new(&model);
+    character.store(None, ids.sword, 2);
+    let events = character.subscribe(None);
+
+    let result = character.batch(|tx| {
+        tx.set_base(ids.level, 5);
+        tx.equip(ids.sword)?;
+        tx.take(None, ids.sword, 1)?;
+        // Capacity is 22 now, so only three more swords fit
+        tx.store(None, ids.sword, 4)
+    });
+
+    assert_eq!(
+        result,
+        Err(CharacterError::DoesNotFit {
+            item: ids.sword,
+            rest: 1
+        })
+    );
+    assert_eq!(character.get(ids.level), 1);
+    assert_eq!(character.get(ids.health), 15);
+    assert_eq!(character.equipped(ids.sword), 0);
+    assert_eq!(character.amount(None, ids.sword), 2);
+    assert_eq!(character.fill(None), 10);
+    assert!(character.poll(events).is_empty());
+}
+
+#[test]
+fn failed_operations_leave_no_trace() {
+    let (model, ids) = build_model();
+    let mut character = Character::new(&model);
+    character.store(None, ids.sword, 1);
+
+    character
+        .batch(|tx| {
+            assert_eq!(
+                tx.take(None, ids.sword, 2),
+                Err(CharacterError::NotEnough {
+                    item: ids.sword,
+                    missing: 1
+                })
+            );
+            assert_eq!(
+                tx.store(None, ids.sword, 10),
+                Err(CharacterError::DoesNotFit {
+                    item: ids.sword,
+                    rest: 7
+                })
+            );
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(character.amount(None, ids.sword), 1);
+    assert_eq!(character.fill(None), 5);
+}
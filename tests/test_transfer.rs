@@ -0,0 +1,193 @@
+use charsheet::model::*;
+use charsheet::{Character, ItemInventory};
+
+fn build_model() -> Model {
+    let mut model = Model::new();
+
+    let main = model.add_inventory("main", Inventory::new().capacity(20));
+    model.set_main_inventory(main);
+    let pouch = model.add_inventory("pouch", Inventory::new().capacity(6).slots(1));
+    let saddlebags = model.add_inventory(
+        "saddlebags",
+        Inventory::new().capacity(100).weight_percent(0),
+    );
+    let pack = model.add_inventory("pack", Inventory::new().capacity(30));
+
+    model.add_item("pouch", Item::new().set_physical(1, 1).set_inventory(pouch));
+    model.add_item(
+        "mule",
+        Item::new().set_physical(0, 1).set_inventory(saddlebags),
+    );
+    model.add_item(
+        "backpack",
+        Item::new().set_physical(2, 1).set_inventory(pack),
+    );
+    model.add_item("torch", Item::new().set_physical(2, 5));
+    model.add_item("coin", Item::new().set_physical(1, 50));
+
+    model
+}
+
+/// Store a pouch and a mule in the main inventory.
+fn containers(model: &Model, character: &mut Character) -> (ItemInventory, ItemInventory) {
+    let pouch = model.items().id("pouch");
+    let mule = model.items().id("mule");
+
+    character.store(None, pouch, 1);
+    character.store(None, mule, 1);
+    (
+        character.containers(None, pouch)[0],
+        character.containers(None, mule)[0],
+    )
+}
+
+#[test]
+fn transfer_all() {
+    let model = build_model();
+    let torch = model.items().id("torch");
+
+    let mut character = Character::new(&model);
+    let (_, mule) = containers(&model, &mut character);
+    character.store(Some(mule), torch, 6);
+    assert_eq!(character.fill(None), 1);
+
+    assert_eq!(character.transfer(Some(mule), None, torch, 4), 4);
+    assert_eq!(character.amount(Some(mule), torch), 2);
+    assert_eq!(character.amount(None, torch), 4);
+    assert_eq!(character.fill(Some(mule)), 4);
+    assert_eq!(character.fill(None), 9);
+
+    character.undo();
+    assert_eq!(character.amount(Some(mule), torch), 6);
+    assert_eq!(character.amount(None, torch), 0);
+    assert_eq!(character.fill(None), 1);
+}
+
+#[test]
+fn partial_transfer() {
+    let model = build_model();
+    let torch = model.items().id("torch");
+    let coin = model.items().id("coin");
+
+    let mut character = Character::new(&model);
+    let (pouch, mule) = containers(&model, &mut character);
+    character.store(Some(mule), coin, 10);
+    character.store(Some(mule), torch, 5);
+
+    // Capacity and slots of the target apply, nothing moves if nothing fits
+    assert_eq!(character.transfer(Some(mule), Some(pouch), coin, 4), 4);
+    assert_eq!(character.transfer(Some(mule), Some(pouch), torch, 1), 0);
+    assert_eq!(character.transfer(Some(mule), Some(pouch), coin, 6), 2);
+    assert_eq!(character.amount(Some(mule), torch), 5);
+    assert_eq!(character.fill(Some(mule)), 14);
+
+    // Only 3 of 20 are left in the main inventory
+    character.store(None, torch, 5);
+    assert_eq!(character.fill(None), 17);
+    assert_eq!(character.transfer(Some(mule), None, torch, 5), 1);
+    assert_eq!(character.amount(Some(mule), torch), 4);
+    assert_eq!(character.fill(None), 19);
+
+    // Asking for more than there is moves everything
+    assert_eq!(character.transfer(Some(pouch), Some(mule), coin, 50), 6);
+    assert_eq!(character.fill(Some(pouch)), 0);
+    assert_eq!(character.fill(None), 13);
+}
+
+#[test]
+fn nested_target() {
+    let model = build_model();
+    let coin = model.items().id("coin");
+
+    let mut character = Character::new(&model);
+    let (pouch, _) = containers(&model, &mut character);
+    character.store(None, coin, 19);
+    assert_eq!(character.fill(None), 20);
+
+    // Moving into a nested inventory does not change the total fill
+    assert_eq!(character.transfer(None, Some(pouch), coin, 4), 4);
+    assert_eq!(character.fill(Some(pouch)), 4);
+    assert_eq!(character.fill(None), 20);
+    assert_eq!(character.transfer(Some(pouch), None, coin, 4), 4);
+    assert_eq!(character.fill(None), 20);
+}
+
+#[test]
+fn transfer_containers() {
+    let model = build_model();
+    let backpack = model.items().id("backpack");
+    let torch = model.items().id("torch");
+
+    let mut character = Character::new(&model);
+    let (pouch, mule) = containers(&model, &mut character);
+    character.store(Some(mule), backpack, 2);
+    let packs = character.containers(Some(mule), backpack);
+    character.store(Some(packs[0]), torch, 5);
+    character.store(Some(packs[1]), torch, 10);
+
+    // The last backpack is too heavy, the first one fits
+    assert_eq!(character.transfer(Some(mule), None, backpack, 1), 1);
+    assert_eq!(character.containers(None, backpack), vec![packs[0]]);
+    assert_eq!(character.amount(Some(packs[0]), torch), 5);
+    assert_eq!(character.fill(None), 13);
+    assert_eq!(character.fill(Some(mule)), 22);
+
+    // The moved content is stored inside the new inventory
+    assert_eq!(character.take(Some(packs[0]), torch, 5), 0);
+    assert_eq!(character.fill(None), 3);
+    assert_eq!(character.fill(Some(mule)), 22);
+
+    // Containers do not fit into themselves
+    let mule_item = model.items().id("mule");
+    assert_eq!(character.transfer(None, Some(mule), mule_item, 1), 0);
+    assert_eq!(character.transfer(None, Some(pouch), backpack, 1), 1);
+    assert_eq!(character.fill(Some(pouch)), 2);
+
+    character.undo();
+    character.undo();
+    character.undo();
+    assert_eq!(character.containers(Some(mule), backpack), packs);
+    assert_eq!(character.fill(Some(mule)), 34);
+    assert_eq!(character.fill(None), 1);
+}
+
+#[test]
+fn heavier_target() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new().capacity(10));
+    model.set_main_inventory(main);
+    let bag = model.add_inventory("bag", Inventory::new().weight_percent(200));
+    let bag = model.add_item("bag", Item::new().set_physical(0, 1).set_inventory(bag));
+    let rock = model.add_item("rock", Item::new().set_physical(1, 10));
+
+    let mut character = Character::new(&model);
+    character.store(None, bag, 1);
+    character.store(None, rock, 10);
+    let bag = character.containers(None, bag)[0];
+
+    // Half of the rocks would fit, but the rest could not go back
+    assert_eq!(character.transfer(None, Some(bag), rock, 10), 0);
+    assert_eq!(character.amount(None, rock), 10);
+    assert_eq!(character.amount(Some(bag), rock), 0);
+    assert_eq!(character.fill(None), 10);
+
+    assert_eq!(character.take(None, rock, 5), 0);
+    assert_eq!(character.transfer(None, Some(bag), rock, 5), 5);
+    assert_eq!(character.fill(None), 10);
+}
+
+#[test]
+fn same_inventory() {
+    let mut model = Model::new();
+    let main = model.add_inventory("main", Inventory::new());
+    model.set_main_inventory(main);
+    let rock = model.add_item("rock", Item::new().set_physical(1, 10));
+
+    let mut character = Character::new(&model);
+    character.store(None, rock, 4);
+    let events = character.subscribe(None);
+
+    assert_eq!(character.transfer(None, None, rock, 4), 0);
+    assert_eq!(character.amount(None, rock), 4);
+    assert!(character.poll(events).is_empty());
+}
@@ -0,0 +1,105 @@
+use charsheet::model::*;
+
+#[test]
+fn valid_model() {
+    let mut model = Model::new();
+    let strength = model.add_value("strength", Value::new(10));
+    let modifier = model.add_value("strength_mod", Value::new(0));
+    model.add_dependency(modifier, strength / 2 - 5);
+
+    let inventory = model.add_inventory("main", Inventory::new().capacity(strength * 10));
+    model.set_main_inventory(inventory);
+    model.add_item("chestplate", Item::new().set_physical(10, 1));
+
+    assert_eq!(model.validate(), vec![]);
+}
+
+#[test]
+fn calculations() {
+    let mut model = Model::new();
+    let strength = model.add_value("strength", Value::new(10));
+    let level = model.add_value("level", Value::new(1));
+    let hit_dice = model.add_value("hit_dice", Value::new(1));
+    model.add_dependency(strength, level / (3 - 3));
+    model.add_dependency(hit_dice, 10 % Calculation::from(level));
+
+    let diagnostics = model.validate();
+    assert_eq!(
+        diagnostics,
+        vec![
+            Diagnostic {
+                entity: "value `strength`".to_owned(),
+                issue: Issue::DivisionByZero,
+            },
+            Diagnostic {
+                entity: "value `hit_dice`".to_owned(),
+                issue: Issue::PossibleDivisionByZero,
+            },
+        ]
+    );
+    assert_eq!(diagnostics[0].severity(), Severity::Error);
+    assert_eq!(diagnostics[1].severity(), Severity::Warning);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "error: value `strength`: calculation divides by zero"
+    );
+}
+
+#[test]
+fn items_and_choices() {
+    let mut model = Model::new();
+    let bag = model.add_inventory("bag", Inventory::new());
+
+    model.add_item("bag", Item::new().set_inventory(bag));
+    model.add_item(
+        "cursed_bag",
+        Item::new()
+            .set_physical(1, 1)
+            .set_inventory(bag)
+            .set_condition(Calculation::from(1).gt(0)),
+    );
+    let neck = model.add_slot("neck", Slot::new(1));
+    let blessing = model.add_item("blessing", Item::new().occupy(neck));
+    model.add_choice("race", Choice::new());
+    model.add_recovery("dawn", Recovery::new().recharge(blessing));
+
+    let issues: Vec<_> = model
+        .validate()
+        .into_iter()
+        .map(|d| d.to_string())
+        .collect();
+    assert_eq!(
+        issues,
+        vec![
+            "warning: choice `race`: choice has no selections",
+            "warning: recovery `dawn`: recharged item has no charges",
+            "error: item `bag`: item has an inventory but is not physical",
+            "error: item `cursed_bag`: item has an inventory but is conditional",
+            "warning: item `cursed_bag`: condition is always 1",
+            "error: item `blessing`: item occupies slots but is not physical",
+            "error: physical items but no main inventory",
+        ]
+    );
+}
+
+#[test]
+fn unreachable_selections() {
+    let mut model = Model::new();
+    let choice = model.add_choice("feat", Choice::new());
+    for _ in 0..usize::from(u16::MAX) + 3 {
+        model.add_selection(choice, Selection::new(std::iter::empty()));
+    }
+
+    let diagnostics = model.validate();
+    assert_eq!(
+        diagnostics,
+        vec![Diagnostic {
+            entity: "choice `feat`".to_owned(),
+            issue: Issue::UnreachableSelections(2),
+        }]
+    );
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "warning: choice `feat`: 2 selections can not be selected"
+    );
+}
@@ -55,3 +55,32 @@ fn multiple_dependencies() {
     char.set_base(perception, 2);
     assert_eq!(char.get(initiative), 2);
 }
+
+#[test]
+fn diamond_dependencies() {
+    let mut model = Model::new();
+    // Declared in reverse so that ids do not follow the dependency order
+    let total = model.add_value("total", Value::new(0));
+    let right = model.add_value("right", Value::new(0));
+    let left = model.add_value("left", Value::new(0));
+    let root = model.add_value("root", Value::new(1));
+    model.add_dependency(left, 2 * root);
+    model.add_dependency(right, 3 * root);
+    model.add_dependency(total, left + right);
+
+    {
+        let mut char = Character::new(&model);
+        assert_eq!(char.get(total), 5);
+
+        let events = char.subscribe(None);
+        char.set_base(root, 2);
+        assert_eq!(char.get(total), 10);
+        assert_eq!(char.poll(events).len(), 4);
+    }
+
+    // Extending the model afterwards is picked up by new characters
+    let extra = model.add_value("extra", Value::new(0));
+    model.add_dependency(extra, total + root);
+    let char = Character::new(&model);
+    assert_eq!(char.get(extra), 6);
+}